@@ -0,0 +1,27 @@
+//! Ctrl-C handling for parallel runs.
+//!
+//! The signal handler only records the request; `runner::run_parallel` polls
+//! it to stop queued repos and terminate running git process groups.
+
+use anyhow::{Context, Result};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static INTERRUPTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Install the Ctrl-C handler. Must be called at most once per process.
+pub fn install_handler() -> Result<()> {
+    ctrlc::set_handler(|| {
+        INTERRUPTS.fetch_add(1, Ordering::SeqCst);
+    })
+    .context("failed to install Ctrl-C handler")
+}
+
+/// True once Ctrl-C has been pressed
+pub fn requested() -> bool {
+    INTERRUPTS.load(Ordering::SeqCst) > 0
+}
+
+/// True when Ctrl-C has been pressed again, asking to skip the SIGTERM grace period
+pub fn forced() -> bool {
+    INTERRUPTS.load(Ordering::SeqCst) > 1
+}
@@ -0,0 +1,288 @@
+//! Manifests for `git-all clone`: which repos to clone and where.
+//!
+//! Supported formats, detected from the file name or contents:
+//! - plain text: one `<url> [path]` per line, `#` comments
+//! - TOML: `repos = [...]` of URLs or `[[repos]]` tables with `url` and `path`
+//! - YAML: a `repos:` list of URLs or `url:` / `path:` mappings
+//!
+//! A repo's path defaults to the last component of its URL without `.git`.
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::config::expand_home;
+use crate::manifest::read_source;
+
+/// One repo to clone: where from and where to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloneEntry {
+    pub url: String,
+    /// Absolute checkout path
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Plain,
+    Toml,
+    Yaml,
+}
+
+impl Format {
+    fn detect(path: Option<&Path>, contents: &str) -> Self {
+        let extension = path
+            .and_then(Path::extension)
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let first_line = contents
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+            .unwrap_or("");
+
+        match extension.as_str() {
+            "toml" => Format::Toml,
+            "yaml" | "yml" => Format::Yaml,
+            _ if first_line.starts_with('[') || first_line.starts_with("repos =") => Format::Toml,
+            _ if first_line.starts_with("repos:") || first_line.starts_with("---") => Format::Yaml,
+            _ => Format::Plain,
+        }
+    }
+}
+
+/// The TOML and YAML layout
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestFile {
+    repos: Vec<RawEntry>,
+}
+
+/// A bare URL, or a URL with its checkout path
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawEntry {
+    Url(String),
+    Repo(RepoEntry),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RepoEntry {
+    url: String,
+    path: Option<String>,
+}
+
+impl RawEntry {
+    fn into_parts(self) -> (String, Option<String>) {
+        match self {
+            RawEntry::Url(url) => (url, None),
+            RawEntry::Repo(RepoEntry { url, path }) => (url, path),
+        }
+    }
+}
+
+impl ManifestFile {
+    fn into_entries(self) -> Vec<(String, Option<String>)> {
+        self.repos.into_iter().map(RawEntry::into_parts).collect()
+    }
+}
+
+/// Read the repos listed in `source` (a file, or `-` for stdin). Relative
+/// paths resolve against `root`, the directory the repos are cloned into.
+/// Entries are sorted by path. A path listed twice, or inside another
+/// listed path, is an error.
+pub fn read_manifest(source: &str, cwd: &Path, root: &Path) -> Result<Vec<CloneEntry>> {
+    let (path, contents) = read_source(source, cwd)?;
+    let describe = || {
+        path.as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| "stdin".to_string())
+    };
+
+    let entries = match Format::detect(path.as_deref(), &contents) {
+        Format::Plain => parse_plain(&contents),
+        Format::Toml => toml::from_str::<ManifestFile>(&contents)
+            .with_context(|| format!("invalid clone manifest {}", describe()))?
+            .into_entries(),
+        Format::Yaml => serde_yaml_ng::from_str::<ManifestFile>(&contents)
+            .with_context(|| format!("invalid clone manifest {}", describe()))?
+            .into_entries(),
+    };
+
+    let mut repos = Vec::new();
+    for (url, checkout) in entries {
+        let checkout = match checkout {
+            Some(checkout) => checkout.trim_end_matches('/').to_string(),
+            None => default_path(&url)
+                .with_context(|| format!("can't tell where to clone {url}; give it a path"))?,
+        };
+        let path = expand_home(&checkout).unwrap_or_else(|| root.join(&checkout));
+        repos.push(CloneEntry { url, path });
+    }
+    // Sorting by components puts every path right after the paths it's inside
+    repos.sort_by(|a, b| a.path.cmp(&b.path));
+    for pair in repos.windows(2) {
+        let (parent, child) = (&pair[0].path, &pair[1].path);
+        if parent == child {
+            bail!("{} is listed twice in {}", parent.display(), describe());
+        }
+        if child.starts_with(parent) {
+            bail!(
+                "{} is inside {}, also listed in {}; clone it separately",
+                child.display(),
+                parent.display(),
+                describe()
+            );
+        }
+    }
+    Ok(repos)
+}
+
+/// `<url> [path]` per line
+fn parse_plain(contents: &str) -> Vec<(String, Option<String>)> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            let url = fields.next().unwrap_or_default().to_string();
+            (url, fields.next().map(str::to_string))
+        })
+        .collect()
+}
+
+/// `api` for `git@github.com:org/api.git` or `https://github.com/org/api`
+fn default_path(url: &str) -> Option<String> {
+    let trimmed = url.trim_end_matches('/');
+    let name = trimmed.rsplit(['/', ':']).next()?.trim_end_matches(".git");
+    if name.is_empty() || name == trimmed {
+        return None;
+    }
+    Some(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            Format::detect(None, "git@github.com:org/api.git\n"),
+            Format::Plain
+        );
+        assert_eq!(
+            Format::detect(Some(Path::new("/t/repos.toml")), ""),
+            Format::Toml
+        );
+        assert_eq!(
+            Format::detect(None, "# team\n[[repos]]\nurl = \"x\"\n"),
+            Format::Toml
+        );
+        assert_eq!(
+            Format::detect(Some(Path::new("/t/repos.yml")), ""),
+            Format::Yaml
+        );
+        assert_eq!(Format::detect(None, "repos:\n  - x\n"), Format::Yaml);
+    }
+
+    #[test]
+    fn test_default_path() {
+        assert_eq!(
+            default_path("git@github.com:org/api.git").as_deref(),
+            Some("api")
+        );
+        assert_eq!(
+            default_path("https://github.com/org/web/").as_deref(),
+            Some("web")
+        );
+        assert_eq!(default_path("host:tools.git").as_deref(), Some("tools"));
+        assert_eq!(default_path("/srv/git/lib.git").as_deref(), Some("lib"));
+        assert_eq!(default_path("api"), None);
+    }
+
+    #[test]
+    fn test_read_manifest_formats_agree() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("src");
+        let manifests = [
+            (
+                "repos.txt",
+                "# team\ngit@github.com:org/web.git\nhttps://github.com/org/api.git services/api\n",
+            ),
+            (
+                "repos.toml",
+                "repos = [\n  \"git@github.com:org/web.git\",\n  { url = \"https://github.com/org/api.git\", path = \"services/api\" },\n]\n",
+            ),
+            (
+                "repos.yaml",
+                "repos:\n  - git@github.com:org/web.git\n  - url: https://github.com/org/api.git\n    path: services/api/\n",
+            ),
+        ];
+        for (name, contents) in manifests {
+            fs::write(temp.path().join(name), contents).unwrap();
+            let entries = read_manifest(name, temp.path(), &root).unwrap();
+            assert_eq!(
+                entries,
+                vec![
+                    CloneEntry {
+                        url: "https://github.com/org/api.git".to_string(),
+                        path: root.join("services/api"),
+                    },
+                    CloneEntry {
+                        url: "git@github.com:org/web.git".to_string(),
+                        path: root.join("web"),
+                    },
+                ],
+                "for {name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_manifest_rejects_duplicates_nesting_and_typos() {
+        let temp = tempfile::tempdir().unwrap();
+        fs::write(
+            temp.path().join("dup.txt"),
+            "git@github.com:org/api.git\ngit@gitlab.com:fork/api.git\n",
+        )
+        .unwrap();
+        let err = read_manifest("dup.txt", temp.path(), temp.path()).unwrap_err();
+        assert!(err.to_string().contains("listed twice"), "{err}");
+
+        fs::write(
+            temp.path().join("nested.txt"),
+            "git@github.com:org/app.git\ngit@github.com:org/app-docs.git\ngit@github.com:org/plugin.git app/plugins/plugin\n",
+        )
+        .unwrap();
+        let err = read_manifest("nested.txt", temp.path(), temp.path()).unwrap_err();
+        assert!(
+            err.to_string().contains(&format!(
+                "{} is inside {}",
+                temp.path().join("app/plugins/plugin").display(),
+                temp.path().join("app").display()
+            )),
+            "{err}"
+        );
+
+        for (name, contents) in [
+            (
+                "typo.toml",
+                "[[repo]]\nurl = \"git@github.com:org/api.git\"\n",
+            ),
+            (
+                "typo.yaml",
+                "repos:\n  - url: git@github.com:org/api.git\n    pth: api\n",
+            ),
+        ] {
+            fs::write(temp.path().join(name), contents).unwrap();
+            assert!(
+                read_manifest(name, temp.path(), temp.path()).is_err(),
+                "{name}"
+            );
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use anyhow::Result;
+use serde_json::{Map, Value, json};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Output;
+
+use crate::clone_manifest::CloneEntry;
+use crate::runner::{
+    ExecutionContext, GitCommand, OutputFormatter, RepoOutcome, Report, run_parallel,
+};
+use crate::url_rewrite::rewrite_url;
+
+/// How a checkout that is already there is reported
+fn exists() -> Report {
+    let mut fields = Map::new();
+    fields.insert("clone".to_string(), json!("exists"));
+    Report {
+        message: "exists".to_string(),
+        fields,
+    }
+}
+
+struct CloneFormatter;
+
+impl OutputFormatter for CloneFormatter {
+    fn format(&self, output: &Output) -> String {
+        if !output.status.success() {
+            // Skip git's `Cloning into '...'` progress line
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return stderr
+                .lines()
+                .find(|line| line.starts_with("fatal:") || line.starts_with("error:"))
+                .or_else(|| stderr.lines().rfind(|line| !line.trim().is_empty()))
+                .unwrap_or("unknown error")
+                .to_string();
+        }
+        "cloned".to_string()
+    }
+
+    fn fields(&self, output: &Output) -> Map<String, Value> {
+        let mut fields = Map::new();
+        if output.status.success() {
+            fields.insert("clone".to_string(), json!("cloned"));
+        }
+        fields
+    }
+}
+
+/// Clone each of `repos` (checkout paths from `entries`) that isn't there
+/// yet. Existing checkouts are reported without running git.
+pub fn run(
+    ctx: &mut ExecutionContext,
+    repos: &[PathBuf],
+    entries: &[CloneEntry],
+    root: &Path,
+    extra_args: &[String],
+) -> Result<Vec<RepoOutcome>> {
+    let formatter = CloneFormatter;
+    let opts = ctx.git_invocation_options();
+    let urls: HashMap<&Path, &str> = entries
+        .iter()
+        .map(|entry| (entry.path.as_path(), entry.url.as_str()))
+        .collect();
+    let existing: HashSet<&PathBuf> = repos
+        .iter()
+        .filter(|repo| repo.join(".git").exists())
+        .collect();
+
+    run_parallel(
+        ctx,
+        repos,
+        |repo| {
+            if existing.contains(repo) {
+                return GitCommand::settled(repo.clone(), exists());
+            }
+            // Rewrite up front so the new checkout's origin uses the forced scheme
+            let url = urls[repo.as_path()];
+            let url = match opts.url_scheme {
+                Some(scheme) => rewrite_url(url, scheme, &opts.url_hosts),
+                None => url.to_string(),
+            };
+            let mut args = vec!["clone".to_string()];
+            args.extend(extra_args.iter().cloned());
+            args.push(url.clone());
+            args.push(repo.to_string_lossy().into_owned());
+            GitCommand::new(root.to_path_buf(), args).with_remote_url(url)
+        },
+        &formatter,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    fn make_output(stdout: &str, stderr: &str, success: bool) -> Output {
+        Output {
+            status: ExitStatus::from_raw(if success { 0 } else { 32768 }),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_cloned_and_exists() {
+        let cloned = make_output("", "Cloning into '/src/api'...\n", true);
+        assert_eq!(CloneFormatter.format(&cloned), "cloned");
+        assert_eq!(CloneFormatter.fields(&cloned)["clone"], "cloned");
+
+        let exists = exists();
+        assert_eq!(exists.message, "exists");
+        assert_eq!(exists.fields["clone"], "exists");
+    }
+
+    #[test]
+    fn test_error_skips_progress_line() {
+        let output = make_output(
+            "",
+            "Cloning into '/src/api'...\nfatal: repository 'https://github.com/org/api.git/' not found\n",
+            false,
+        );
+        assert_eq!(
+            CloneFormatter.format(&output),
+            "fatal: repository 'https://github.com/org/api.git/' not found"
+        );
+        assert!(CloneFormatter.fields(&output).is_empty());
+    }
+}
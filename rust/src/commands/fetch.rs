@@ -1,8 +1,9 @@
 use anyhow::Result;
+use serde_json::{Map, Value, json};
 use std::path::PathBuf;
 use std::process::Output;
 
-use crate::runner::{ExecutionContext, GitCommand, OutputFormatter, run_parallel};
+use crate::runner::{ExecutionContext, GitCommand, OutputFormatter, RepoOutcome, run_parallel};
 
 struct FetchFormatter;
 
@@ -24,16 +25,7 @@ impl OutputFormatter for FetchFormatter {
             return "no new commits".to_string();
         }
 
-        let (branch_count, tag_count) = stdout
-            .lines()
-            .filter(|l| l.contains("->") || l.contains("[new"))
-            .fold((0, 0), |(b, t), l| {
-                if l.contains("[new tag]") {
-                    (b, t + 1)
-                } else {
-                    (b + 1, t)
-                }
-            });
+        let (branch_count, tag_count) = count_updates(&stdout);
 
         if branch_count > 0 || tag_count > 0 {
             let mut parts = Vec::new();
@@ -56,9 +48,38 @@ impl OutputFormatter for FetchFormatter {
 
         "fetched".to_string()
     }
+
+    fn fields(&self, output: &Output) -> Map<String, Value> {
+        let mut fields = Map::new();
+        if !output.status.success() {
+            return fields;
+        }
+        let (branch_count, tag_count) = count_updates(&String::from_utf8_lossy(&output.stdout));
+        fields.insert("branches_updated".to_string(), json!(branch_count));
+        fields.insert("tags_updated".to_string(), json!(tag_count));
+        fields
+    }
 }
 
-pub fn run(ctx: &mut ExecutionContext, repos: &[PathBuf], extra_args: &[String]) -> Result<()> {
+/// Count updated branches and tags in `git fetch` ref update lines
+fn count_updates(stdout: &str) -> (usize, usize) {
+    stdout
+        .lines()
+        .filter(|l| l.contains("->") || l.contains("[new"))
+        .fold((0, 0), |(b, t), l| {
+            if l.contains("[new tag]") {
+                (b, t + 1)
+            } else {
+                (b + 1, t)
+            }
+        })
+}
+
+pub fn run(
+    ctx: &mut ExecutionContext,
+    repos: &[PathBuf],
+    extra_args: &[String],
+) -> Result<Vec<RepoOutcome>> {
     let formatter = FetchFormatter;
 
     run_parallel(
@@ -146,6 +167,15 @@ mod tests {
         assert_eq!(formatter.format(&output), "1 branch, 1 tag updated");
     }
 
+    #[test]
+    fn test_fields_report_branch_and_tag_counts() {
+        let formatter = FetchFormatter;
+        let stdout = "   abc123..def456  main       -> origin/main\n * [new tag]         v1.0.0     -> v1.0.0\n * [new tag]         v1.0.1     -> v1.0.1\n";
+        let fields = formatter.fields(&make_output(stdout, "", true));
+        assert_eq!(fields["branches_updated"], 1);
+        assert_eq!(fields["tags_updated"], 2);
+    }
+
     #[test]
     fn test_fallback_to_fetched() {
         let formatter = FetchFormatter;
@@ -1,4 +1,7 @@
+pub mod clone;
 pub mod fetch;
 pub mod passthrough;
 pub mod pull;
+pub mod push;
 pub mod status;
+pub mod sync;
@@ -2,7 +2,7 @@ use anyhow::Result;
 use std::path::PathBuf;
 use std::process::Output;
 
-use crate::runner::{ExecutionContext, GitCommand, OutputFormatter, run_parallel};
+use crate::runner::{ExecutionContext, GitCommand, OutputFormatter, RepoOutcome, run_parallel};
 
 struct PassthroughFormatter;
 
@@ -27,9 +27,17 @@ impl OutputFormatter for PassthroughFormatter {
             .trim()
             .to_string()
     }
+
+    fn verbatim(&self) -> bool {
+        true
+    }
 }
 
-pub fn run(ctx: &mut ExecutionContext, repos: &[PathBuf], args: &[String]) -> Result<()> {
+pub fn run(
+    ctx: &mut ExecutionContext,
+    repos: &[PathBuf],
+    args: &[String],
+) -> Result<Vec<RepoOutcome>> {
     if args.is_empty() {
         anyhow::bail!("No git command specified");
     }
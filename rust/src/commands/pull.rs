@@ -2,7 +2,7 @@ use anyhow::Result;
 use std::path::PathBuf;
 use std::process::Output;
 
-use crate::runner::{ExecutionContext, GitCommand, OutputFormatter, run_parallel};
+use crate::runner::{ExecutionContext, GitCommand, OutputFormatter, RepoOutcome, run_parallel};
 
 struct PullFormatter;
 
@@ -44,7 +44,11 @@ impl OutputFormatter for PullFormatter {
     }
 }
 
-pub fn run(ctx: &mut ExecutionContext, repos: &[PathBuf], extra_args: &[String]) -> Result<()> {
+pub fn run(
+    ctx: &mut ExecutionContext,
+    repos: &[PathBuf],
+    extra_args: &[String],
+) -> Result<Vec<RepoOutcome>> {
     let formatter = PullFormatter;
 
     run_parallel(
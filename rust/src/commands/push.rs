@@ -0,0 +1,292 @@
+use anyhow::Result;
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Output;
+
+use crate::runner::{
+    ExecutionContext, FollowUpGit, FollowUpResult, GitCommand, OutputFormatter, RepoOutcome,
+    Report, run_parallel,
+};
+
+/// Commits each updated branch gained, keyed by destination ref
+type CommitCounts = HashMap<String, usize>;
+
+/// One ref line of `git push --porcelain`: `<flag>\t<from>:<to>\t<summary>`
+#[derive(Debug, PartialEq, Eq)]
+struct RefUpdate {
+    flag: char,
+    /// Destination ref, e.g. `refs/heads/main`
+    to: String,
+    summary: String,
+}
+
+impl RefUpdate {
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.splitn(3, '\t');
+        let flag = fields.next()?.chars().next()?;
+        let refs = fields.next()?;
+        let summary = fields.next()?.to_string();
+        if !" +-*=!".contains(flag) {
+            return None;
+        }
+        let to = refs.split_once(':').map_or(refs, |(_, to)| to).to_string();
+        Some(RefUpdate { flag, to, summary })
+    }
+
+    /// `main` for `refs/heads/main`, `v1.0` for `refs/tags/v1.0`
+    fn short_name(&self) -> &str {
+        self.to
+            .strip_prefix("refs/heads/")
+            .or_else(|| self.to.strip_prefix("refs/tags/"))
+            .unwrap_or(&self.to)
+    }
+
+    fn is_tag(&self) -> bool {
+        self.to.starts_with("refs/tags/")
+    }
+
+    /// The `old..new` range a fast-forward or forced update moved the ref across
+    fn range(&self) -> Option<(&str, &str)> {
+        let range = self.summary.split_whitespace().next()?;
+        range.split_once("...").or_else(|| range.split_once(".."))
+    }
+
+    /// Why a `!` ref was rejected, e.g. `non-fast-forward`
+    fn rejection(&self) -> &str {
+        self.summary
+            .split_once('(')
+            .map_or(self.summary.as_str(), |(_, reason)| {
+                reason.trim_end_matches(')')
+            })
+    }
+
+    fn describe(&self, commits: Option<usize>) -> String {
+        let name = self.short_name();
+        let commits = match commits {
+            Some(1) => " (1 commit)".to_string(),
+            Some(count) => format!(" ({count} commits)"),
+            None => String::new(),
+        };
+        match self.flag {
+            '*' if self.is_tag() => format!("pushed tag {name}"),
+            '*' => format!("pushed new branch {name}"),
+            '+' => format!("force-pushed {name}{commits}"),
+            '-' => format!("deleted {name}"),
+            _ => format!("pushed {name}{commits}"),
+        }
+    }
+}
+
+fn ref_updates(stdout: &str) -> Vec<RefUpdate> {
+    stdout.lines().filter_map(RefUpdate::parse).collect()
+}
+
+/// After a successful push, count the commits each updated branch gained
+fn count_commits(git: &FollowUpGit, pushed: &Output) -> std::io::Result<FollowUpResult> {
+    let mut counts = CommitCounts::new();
+    for update in ref_updates(&String::from_utf8_lossy(&pushed.stdout)) {
+        let Some((old, new)) = update.range().filter(|_| " +".contains(update.flag)) else {
+            continue;
+        };
+        let count = git.run(&["rev-list", "--count", &format!("{old}..{new}")])?;
+        if let Ok(count) = String::from_utf8_lossy(&count.stdout).trim().parse() {
+            counts.insert(update.to.clone(), count);
+        }
+    }
+    Ok(FollowUpResult::Report(Report {
+        message: PushFormatter::summary(pushed, &counts),
+        fields: PushFormatter::ref_fields(pushed, &counts),
+    }))
+}
+
+struct PushFormatter;
+
+impl PushFormatter {
+    fn summary(output: &Output, counts: &CommitCounts) -> String {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let updates = ref_updates(&stdout);
+
+        if let Some(rejected) = updates.iter().find(|update| update.flag == '!') {
+            return format!("rejected: {}", rejected.rejection());
+        }
+        if stderr.contains("has no upstream branch") {
+            return "no upstream".to_string();
+        }
+        if !output.status.success() {
+            return stderr.lines().next().unwrap_or("unknown error").to_string();
+        }
+
+        let pushed: Vec<String> = updates
+            .iter()
+            .filter(|update| update.flag != '=')
+            .map(|update| update.describe(counts.get(&update.to).copied()))
+            .collect();
+        if pushed.is_empty() {
+            "up to date".to_string()
+        } else {
+            pushed.join(", ")
+        }
+    }
+
+    fn ref_fields(output: &Output, counts: &CommitCounts) -> Map<String, Value> {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let refs: Vec<Value> = ref_updates(&stdout)
+            .iter()
+            .map(|update| {
+                let result = match update.flag {
+                    '=' => "up-to-date",
+                    '!' => "rejected",
+                    '-' => "deleted",
+                    '+' => "forced",
+                    '*' => "new",
+                    _ => "pushed",
+                };
+                let mut record = Map::new();
+                record.insert("ref".to_string(), json!(update.to));
+                record.insert("result".to_string(), json!(result));
+                if update.flag == '!' {
+                    record.insert("reason".to_string(), json!(update.rejection()));
+                }
+                if let Some(count) = counts.get(&update.to) {
+                    record.insert("commits".to_string(), json!(count));
+                }
+                Value::Object(record)
+            })
+            .collect();
+
+        let mut fields = Map::new();
+        fields.insert("refs".to_string(), Value::Array(refs));
+        fields
+    }
+}
+
+/// Formats pushes the follow-up didn't report on: failures and rejections
+impl OutputFormatter for PushFormatter {
+    fn format(&self, output: &Output) -> String {
+        Self::summary(output, &CommitCounts::new())
+    }
+
+    fn fields(&self, output: &Output) -> Map<String, Value> {
+        Self::ref_fields(output, &CommitCounts::new())
+    }
+}
+
+pub fn run(
+    ctx: &mut ExecutionContext,
+    repos: &[PathBuf],
+    extra_args: &[String],
+) -> Result<Vec<RepoOutcome>> {
+    let formatter = PushFormatter;
+
+    run_parallel(
+        ctx,
+        repos,
+        |repo| {
+            let mut args = vec!["push".to_string(), "--porcelain".to_string()];
+            args.extend(extra_args.iter().cloned());
+            GitCommand::new(repo.clone(), args).with_follow_up_step(count_commits)
+        },
+        &formatter,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    fn make_output(stdout: &str, stderr: &str, success: bool) -> Output {
+        Output {
+            status: ExitStatus::from_raw(if success { 0 } else { 256 }),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_pushed_with_commit_count() {
+        let stdout = "To github.com:org/repo.git\n \trefs/heads/main:refs/heads/main\tb08e16e..43135a4\nDone\n";
+        let output = make_output(stdout, "", true);
+        let counts = CommitCounts::from([("refs/heads/main".to_string(), 3)]);
+        assert_eq!(
+            PushFormatter::summary(&output, &counts),
+            "pushed main (3 commits)"
+        );
+        let fields = PushFormatter::ref_fields(&output, &counts);
+        assert_eq!(fields["refs"][0]["ref"], "refs/heads/main");
+        assert_eq!(fields["refs"][0]["result"], "pushed");
+        assert_eq!(fields["refs"][0]["commits"], 3);
+
+        assert_eq!(PushFormatter.format(&output), "pushed main");
+        assert!(
+            PushFormatter.fields(&output)["refs"][0]
+                .get("commits")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_range() {
+        let forced =
+            RefUpdate::parse("+\trefs/heads/wip:refs/heads/wip\t1111111...2222222 (forced update)")
+                .unwrap();
+        assert_eq!(forced.range(), Some(("1111111", "2222222")));
+        let new = RefUpdate::parse("*\trefs/heads/feat:refs/heads/feat\t[new branch]").unwrap();
+        assert_eq!(new.range(), None);
+    }
+
+    #[test]
+    fn test_up_to_date() {
+        let stdout =
+            "To github.com:org/repo.git\n=\trefs/heads/main:refs/heads/main\t[up to date]\nDone\n";
+        let output = make_output(stdout, "", true);
+        assert_eq!(PushFormatter.format(&output), "up to date");
+        assert_eq!(
+            PushFormatter.format(&make_output("", "Everything up-to-date\n", true)),
+            "up to date"
+        );
+    }
+
+    #[test]
+    fn test_rejected_reports_reason() {
+        let stdout = "To github.com:org/repo.git\n!\trefs/heads/main:refs/heads/main\t[rejected] (non-fast-forward)\nDone\n";
+        let output = make_output(stdout, "error: failed to push some refs\n", false);
+        assert_eq!(PushFormatter.format(&output), "rejected: non-fast-forward");
+        let fields = PushFormatter.fields(&output);
+        assert_eq!(fields["refs"][0]["reason"], "non-fast-forward");
+    }
+
+    #[test]
+    fn test_no_upstream() {
+        let stderr = "fatal: The current branch feat has no upstream branch.\nTo push the current branch...\n";
+        let output = make_output("", stderr, false);
+        assert_eq!(PushFormatter.format(&output), "no upstream");
+    }
+
+    #[test]
+    fn test_new_refs_forced_and_deleted() {
+        let stdout = "To github.com:org/repo.git\n*\trefs/heads/feat:refs/heads/feat\t[new branch]\n*\trefs/tags/v1.0:refs/tags/v1.0\t[new tag]\n+\trefs/heads/wip:refs/heads/wip\t1111111...2222222 (forced update)\n-\t:refs/heads/old\t[deleted]\nDone\n";
+        let output = make_output(stdout, "", true);
+        let counts = CommitCounts::from([("refs/heads/wip".to_string(), 2)]);
+        assert_eq!(
+            PushFormatter::summary(&output, &counts),
+            "pushed new branch feat, pushed tag v1.0, force-pushed wip (2 commits), deleted old"
+        );
+    }
+
+    #[test]
+    fn test_error_returns_first_stderr_line() {
+        let output = make_output(
+            "",
+            "fatal: 'origin' does not appear to be a git repository\n",
+            false,
+        );
+        assert_eq!(
+            PushFormatter.format(&output),
+            "fatal: 'origin' does not appear to be a git repository"
+        );
+    }
+}
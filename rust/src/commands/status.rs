@@ -1,27 +1,38 @@
 use anyhow::Result;
+use serde_json::{Map, Value, json};
 use std::path::PathBuf;
 use std::process::Output;
 
-use crate::runner::{ExecutionContext, GitCommand, OutputFormatter, run_parallel};
+use crate::repo::DETACHED_HEAD;
+use crate::runner::{ExecutionContext, GitCommand, OutputFormatter, RepoOutcome, run_parallel};
 
 struct StatusFormatter;
 
-impl OutputFormatter for StatusFormatter {
-    fn format(&self, output: &Output) -> String {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
-
-        if !output.status.success() {
-            return stderr.lines().next().unwrap_or("unknown error").to_string();
-        }
+/// Parsed `git status --porcelain -b` output
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct StatusSummary {
+    pub branch: Option<String>,
+    /// Tracking branch, `None` when unset or gone
+    pub upstream: Option<String>,
+    pub modified: usize,
+    pub added: usize,
+    pub deleted: usize,
+    pub renamed: usize,
+    pub untracked: usize,
+    pub ahead: usize,
+    pub behind: usize,
+}
 
-        let mut modified = 0;
-        let mut added = 0;
-        let mut deleted = 0;
-        let mut untracked = 0;
-        let mut renamed = 0;
+impl StatusSummary {
+    pub fn parse(stdout: &str) -> Self {
+        let mut summary = StatusSummary::default();
 
         for line in stdout.lines() {
+            if let Some(header) = line.strip_prefix("## ") {
+                summary.parse_branch_header(header);
+                continue;
+            }
+
             if line.len() < 2 {
                 continue;
             }
@@ -30,66 +41,306 @@ impl OutputFormatter for StatusFormatter {
             let worktree_status = line.chars().nth(1).unwrap_or(' ');
 
             if index_status == '?' {
-                untracked += 1;
+                summary.untracked += 1;
                 continue;
             }
 
             match index_status {
-                'M' => modified += 1,
-                'A' => added += 1,
-                'D' => deleted += 1,
-                'R' => renamed += 1,
+                'M' => summary.modified += 1,
+                'A' => summary.added += 1,
+                'D' => summary.deleted += 1,
+                'R' => summary.renamed += 1,
                 _ => {}
             }
 
             // Check worktree status (unstaged changes) - only if not already counted
             if index_status == ' ' {
                 match worktree_status {
-                    'M' => modified += 1,
-                    'D' => deleted += 1,
+                    'M' => summary.modified += 1,
+                    'D' => summary.deleted += 1,
                     _ => {}
                 }
             }
         }
 
-        if modified == 0 && added == 0 && deleted == 0 && untracked == 0 && renamed == 0 {
-            return "clean".to_string();
+        summary
+    }
+
+    /// Parse the `## branch...upstream [ahead N, behind M]` header line
+    fn parse_branch_header(&mut self, header: &str) {
+        if header.starts_with("HEAD (no branch)") {
+            self.branch = Some(DETACHED_HEAD.to_string());
+            return;
+        }
+
+        let (refs, tracking) = match header.split_once(" [") {
+            Some((refs, rest)) => (refs, rest.trim_end_matches(']')),
+            None => (header, ""),
+        };
+
+        let (local, upstream) = match refs.split_once("...") {
+            Some((local, upstream)) => (local, Some(upstream)),
+            None => (refs, None),
+        };
+        if tracking != "gone" {
+            self.upstream = upstream.map(str::to_string);
         }
+        let local = local
+            .strip_prefix("No commits yet on ")
+            .or_else(|| local.strip_prefix("Initial commit on "))
+            .unwrap_or(local);
+        self.branch = Some(local.to_string());
 
+        for part in tracking.split(", ") {
+            if let Some(count) = part.strip_prefix("ahead ") {
+                self.ahead = count.parse().unwrap_or(0);
+            } else if let Some(count) = part.strip_prefix("behind ") {
+                self.behind = count.parse().unwrap_or(0);
+            }
+        }
+    }
+
+    /// Whether the worktree has any modified, staged or untracked files
+    pub fn is_dirty(&self) -> bool {
+        self.modified + self.added + self.deleted + self.renamed + self.untracked > 0
+    }
+
+    /// Structured counts for JSON output
+    fn fields(&self) -> Map<String, Value> {
+        let mut fields = Map::new();
+        fields.insert("branch".to_string(), json!(self.branch));
+        fields.insert("modified".to_string(), json!(self.modified));
+        fields.insert("added".to_string(), json!(self.added));
+        fields.insert("deleted".to_string(), json!(self.deleted));
+        fields.insert("renamed".to_string(), json!(self.renamed));
+        fields.insert("untracked".to_string(), json!(self.untracked));
+        fields.insert("ahead".to_string(), json!(self.ahead));
+        fields.insert("behind".to_string(), json!(self.behind));
+        fields
+    }
+
+    /// Human-readable message in SPEC 7.2.1 order
+    pub fn message(&self) -> String {
         let mut parts = Vec::new();
 
-        if modified > 0 {
-            parts.push(format!("{} modified", modified));
+        if self.modified > 0 {
+            parts.push(format!("{} modified", self.modified));
         }
-        if added > 0 {
-            parts.push(format!("{} added", added));
+        if self.added > 0 {
+            parts.push(format!("{} added", self.added));
         }
-        if deleted > 0 {
-            parts.push(format!("{} deleted", deleted));
+        if self.deleted > 0 {
+            parts.push(format!("{} deleted", self.deleted));
         }
-        if renamed > 0 {
-            parts.push(format!("{} renamed", renamed));
+        if self.renamed > 0 {
+            parts.push(format!("{} renamed", self.renamed));
         }
-        if untracked > 0 {
-            parts.push(format!("{} untracked", untracked));
+        if self.untracked > 0 {
+            parts.push(format!("{} untracked", self.untracked));
+        }
+        if parts.is_empty() {
+            parts.push("clean".to_string());
+        }
+        if self.ahead > 0 {
+            parts.push(format!("{} ahead", self.ahead));
+        }
+        if self.behind > 0 {
+            parts.push(format!("{} behind", self.behind));
         }
 
         parts.join(", ")
     }
 }
 
-pub fn run(ctx: &mut ExecutionContext, repos: &[PathBuf], extra_args: &[String]) -> Result<()> {
+impl OutputFormatter for StatusFormatter {
+    fn format(&self, output: &Output) -> String {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        if !output.status.success() {
+            return stderr.lines().next().unwrap_or("unknown error").to_string();
+        }
+
+        StatusSummary::parse(&stdout).message()
+    }
+
+    fn fields(&self, output: &Output) -> Map<String, Value> {
+        if !output.status.success() {
+            return Map::new();
+        }
+        StatusSummary::parse(&String::from_utf8_lossy(&output.stdout)).fields()
+    }
+}
+
+pub fn run(
+    ctx: &mut ExecutionContext,
+    repos: &[PathBuf],
+    extra_args: &[String],
+) -> Result<Vec<RepoOutcome>> {
     let formatter = StatusFormatter;
 
     run_parallel(
         ctx,
         repos,
         |repo| {
-            // Always use --porcelain for machine-readable output
-            let mut args = vec!["status".to_string(), "--porcelain".to_string()];
+            // Always use --porcelain -b for machine-readable output with branch tracking
+            let mut args = vec![
+                "status".to_string(),
+                "--porcelain".to_string(),
+                "-b".to_string(),
+            ];
             args.extend(extra_args.iter().cloned());
             GitCommand::new(repo.clone(), args)
         },
         &formatter,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    fn make_output(stdout: &str, stderr: &str, success: bool) -> Output {
+        Output {
+            status: ExitStatus::from_raw(if success { 0 } else { 256 }),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+
+    /// SPEC 7.2.3 expected output table: (porcelain input, branch, message)
+    const SPEC_TABLE: &[(&str, Option<&str>, &str)] = &[
+        ("", None, "clean"),
+        ("## main", Some("main"), "clean"),
+        ("## main...origin/main", Some("main"), "clean"),
+        ("## HEAD (no branch)", Some("HEAD (detached)"), "clean"),
+        ("## main\n M file.txt", Some("main"), "1 modified"),
+        (
+            "## main\n M a.txt\n M b.txt\n M c.txt",
+            Some("main"),
+            "3 modified",
+        ),
+        ("## main\nM  file.txt", Some("main"), "1 modified"),
+        ("## main\nMM file.txt", Some("main"), "1 modified"),
+        ("## main\nA  file.txt", Some("main"), "1 added"),
+        ("## main\nAM file.txt", Some("main"), "1 added"),
+        ("## main\nD  file.txt", Some("main"), "1 deleted"),
+        ("## main\n D file.txt", Some("main"), "1 deleted"),
+        ("## main\nR  old.txt -> new.txt", Some("main"), "1 renamed"),
+        ("## main\n?? file.txt", Some("main"), "1 untracked"),
+        ("## main\n?? a.txt\n?? b.txt", Some("main"), "2 untracked"),
+        (
+            "## main\n M mod.txt\n?? new.txt",
+            Some("main"),
+            "1 modified, 1 untracked",
+        ),
+        (
+            "## main\n M mod.txt\nA  add.txt\n?? new.txt",
+            Some("main"),
+            "1 modified, 1 added, 1 untracked",
+        ),
+        (
+            "## main\nM  a.txt\nA  b.txt\nD  c.txt\nR  d.txt -> e.txt\n?? f.txt",
+            Some("main"),
+            "1 modified, 1 added, 1 deleted, 1 renamed, 1 untracked",
+        ),
+        (
+            "## main...origin/main [ahead 2]",
+            Some("main"),
+            "clean, 2 ahead",
+        ),
+        (
+            "## main...origin/main [behind 3]",
+            Some("main"),
+            "clean, 3 behind",
+        ),
+        (
+            "## main...origin/main [ahead 2, behind 3]",
+            Some("main"),
+            "clean, 2 ahead, 3 behind",
+        ),
+        (
+            "## main...origin/main [ahead 1]\n M file.txt",
+            Some("main"),
+            "1 modified, 1 ahead",
+        ),
+        (
+            "## feat...origin/feat [ahead 2, behind 1]\n M a.txt\n?? b.txt",
+            Some("feat"),
+            "1 modified, 1 untracked, 2 ahead, 1 behind",
+        ),
+    ];
+
+    #[test]
+    fn test_spec_expected_output_table() {
+        let formatter = StatusFormatter;
+        for (input, branch, message) in SPEC_TABLE {
+            let summary = StatusSummary::parse(input);
+            assert_eq!(
+                summary.branch.as_deref(),
+                *branch,
+                "branch for input {input:?}"
+            );
+            assert_eq!(
+                formatter.format(&make_output(input, "", true)),
+                *message,
+                "message for input {input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_fields_report_counts_and_branch() {
+        let formatter = StatusFormatter;
+        let output = make_output(
+            "## feat...origin/feat [ahead 2, behind 1]\n M a.txt\n?? b.txt",
+            "",
+            true,
+        );
+        let fields = formatter.fields(&output);
+        assert_eq!(fields["branch"], "feat");
+        assert_eq!(fields["modified"], 1);
+        assert_eq!(fields["untracked"], 1);
+        assert_eq!(fields["added"], 0);
+        assert_eq!(fields["ahead"], 2);
+        assert_eq!(fields["behind"], 1);
+    }
+
+    #[test]
+    fn test_is_dirty() {
+        assert!(!StatusSummary::parse("## main...origin/main [behind 2]").is_dirty());
+        assert!(StatusSummary::parse("## main\n?? new.txt").is_dirty());
+        assert!(StatusSummary::parse("## main\nR  a -> b").is_dirty());
+    }
+
+    #[test]
+    fn test_unborn_branch_header() {
+        let summary = StatusSummary::parse("## No commits yet on main\n?? a.txt");
+        assert_eq!(summary.branch.as_deref(), Some("main"));
+        assert_eq!(summary.message(), "1 untracked");
+    }
+
+    #[test]
+    fn test_gone_upstream_has_no_counts() {
+        let summary = StatusSummary::parse("## main...origin/main [gone]");
+        assert_eq!(summary.branch.as_deref(), Some("main"));
+        assert_eq!(summary.upstream, None);
+        assert_eq!(summary.message(), "clean");
+    }
+
+    #[test]
+    fn test_upstream() {
+        let summary = StatusSummary::parse("## main...origin/main [behind 2]");
+        assert_eq!(summary.upstream.as_deref(), Some("origin/main"));
+        assert_eq!(StatusSummary::parse("## main").upstream, None);
+    }
+
+    #[test]
+    fn test_error_returns_first_stderr_line() {
+        let formatter = StatusFormatter;
+        let output = make_output("", "fatal: not a git repository\nmore", false);
+        assert_eq!(formatter.format(&output), "fatal: not a git repository");
+    }
+}
@@ -0,0 +1,212 @@
+use anyhow::Result;
+use serde_json::{Map, Value, json};
+use std::path::PathBuf;
+use std::process::Output;
+
+use crate::commands::status::StatusSummary;
+use crate::repo::DETACHED_HEAD;
+use crate::runner::{
+    ExecutionContext, FollowUpGit, FollowUpResult, GitCommand, OutputFormatter, RepoOutcome,
+    Report, run_parallel,
+};
+
+/// What to do with a repo's current branch once its fetch is done
+#[derive(Debug, PartialEq, Eq)]
+enum Plan {
+    /// Clean and strictly behind: fast-forward this many commits
+    FastForward(usize),
+    /// Nothing to pull; the branch may still be ahead
+    UpToDate { ahead: usize },
+    /// Not safe to touch, with the reason shown to the user
+    Skip(String),
+}
+
+impl Plan {
+    fn for_status(status: &StatusSummary) -> Self {
+        match status.branch.as_deref() {
+            None | Some(DETACHED_HEAD) => return Plan::Skip("detached".to_string()),
+            Some(_) => {}
+        }
+        if status.upstream.is_none() {
+            return Plan::Skip("no upstream".to_string());
+        }
+        if status.is_dirty() {
+            return Plan::Skip("dirty".to_string());
+        }
+        match (status.ahead, status.behind) {
+            (ahead, behind) if ahead > 0 && behind > 0 => {
+                Plan::Skip(format!("diverged {ahead}/{behind}"))
+            }
+            (_, 0) => Plan::UpToDate {
+                ahead: status.ahead,
+            },
+            (_, behind) => Plan::FastForward(behind),
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            Plan::FastForward(1) => "fast-forwarded 1 commit".to_string(),
+            Plan::FastForward(commits) => format!("fast-forwarded {commits} commits"),
+            Plan::UpToDate { ahead: 0 } => "up to date".to_string(),
+            Plan::UpToDate { ahead } => format!("up to date, {ahead} ahead"),
+            Plan::Skip(reason) => format!("skipped: {reason}"),
+        }
+    }
+
+    fn fields(&self) -> Map<String, Value> {
+        let mut fields = Map::new();
+        match self {
+            Plan::FastForward(commits) => {
+                fields.insert("sync".to_string(), json!("fast-forwarded"));
+                fields.insert("commits".to_string(), json!(commits));
+            }
+            Plan::UpToDate { .. } => {
+                fields.insert("sync".to_string(), json!("up-to-date"));
+            }
+            Plan::Skip(reason) => {
+                fields.insert("sync".to_string(), json!("skipped"));
+                fields.insert("reason".to_string(), json!(reason));
+            }
+        }
+        fields
+    }
+}
+
+/// After a successful fetch, fast-forward the current branch when that's safe
+/// and report what happened
+fn fast_forward(git: &FollowUpGit, _fetched: &Output) -> std::io::Result<FollowUpResult> {
+    let status = git.run(&["--no-optional-locks", "status", "--porcelain", "-b"])?;
+    if !status.status.success() {
+        return Ok(FollowUpResult::Failed(status));
+    }
+    let plan = Plan::for_status(&StatusSummary::parse(&String::from_utf8_lossy(
+        &status.stdout,
+    )));
+
+    if let Plan::FastForward(_) = plan {
+        let merge = git.run(&["merge", "--ff-only", "--quiet", "@{upstream}"])?;
+        if !merge.status.success() {
+            return Ok(FollowUpResult::Failed(merge));
+        }
+    }
+
+    Ok(FollowUpResult::Report(Report {
+        message: plan.message(),
+        fields: plan.fields(),
+    }))
+}
+
+/// Formats the fetch itself; a successful fetch is reported by `fast_forward`
+struct SyncFormatter;
+
+impl OutputFormatter for SyncFormatter {
+    fn format(&self, output: &Output) -> String {
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return stderr.lines().next().unwrap_or("unknown error").to_string();
+        }
+        "fetched".to_string()
+    }
+}
+
+pub fn run(
+    ctx: &mut ExecutionContext,
+    repos: &[PathBuf],
+    extra_args: &[String],
+) -> Result<Vec<RepoOutcome>> {
+    let formatter = SyncFormatter;
+
+    run_parallel(
+        ctx,
+        repos,
+        |repo| {
+            let mut args = vec!["fetch".to_string()];
+            args.extend(extra_args.iter().cloned());
+            GitCommand::new(repo.clone(), args).with_follow_up_step(fast_forward)
+        },
+        &formatter,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    fn make_output(stdout: &str, stderr: &str, success: bool) -> Output {
+        Output {
+            status: ExitStatus::from_raw(if success { 0 } else { 256 }),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+
+    fn plan(porcelain: &str) -> Plan {
+        Plan::for_status(&StatusSummary::parse(porcelain))
+    }
+
+    #[test]
+    fn test_plan_messages() {
+        let cases = [
+            (
+                "## main...origin/main [behind 3]",
+                "fast-forwarded 3 commits",
+            ),
+            (
+                "## main...origin/main [behind 1]",
+                "fast-forwarded 1 commit",
+            ),
+            ("## main...origin/main", "up to date"),
+            ("## main...origin/main [ahead 2]", "up to date, 2 ahead"),
+            (
+                "## main...origin/main [behind 3]\n M a.txt",
+                "skipped: dirty",
+            ),
+            (
+                "## main...origin/main [behind 3]\n?? new.txt",
+                "skipped: dirty",
+            ),
+            (
+                "## main...origin/main [ahead 2, behind 3]",
+                "skipped: diverged 2/3",
+            ),
+            ("## main", "skipped: no upstream"),
+            ("## main...origin/main [gone]", "skipped: no upstream"),
+            ("## HEAD (no branch)", "skipped: detached"),
+        ];
+        for (porcelain, expected) in cases {
+            assert_eq!(plan(porcelain).message(), expected, "for {porcelain:?}");
+        }
+    }
+
+    #[test]
+    fn test_plan_fields() {
+        let fields = plan("## main...origin/main [behind 2]").fields();
+        assert_eq!(fields["sync"], "fast-forwarded");
+        assert_eq!(fields["commits"], 2);
+
+        let fields = plan("## main...origin/main [ahead 2]").fields();
+        assert_eq!(fields["sync"], "up-to-date");
+
+        let fields = plan("## main...origin/main [ahead 2, behind 3]").fields();
+        assert_eq!(fields["sync"], "skipped");
+        assert_eq!(fields["reason"], "diverged 2/3");
+    }
+
+    #[test]
+    fn test_error_returns_first_stderr_line() {
+        let formatter = SyncFormatter;
+        let output = make_output(
+            "",
+            "fatal: 'origin' does not appear to be a git repository\nmore",
+            false,
+        );
+        assert_eq!(
+            formatter.format(&output),
+            "fatal: 'origin' does not appear to be a git repository"
+        );
+        assert!(formatter.fields(&output).is_empty());
+    }
+}
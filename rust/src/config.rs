@@ -0,0 +1,793 @@
+//! Layered defaults from config files.
+//!
+//! Precedence, highest first:
+//! 1. the command line (and `GIT_ALL_*` environment variables)
+//! 2. the workspace `.git-all.toml` in the working directory or its nearest ancestor
+//! 3. the user's `~/.config/git-all/config.toml` (`$XDG_CONFIG_HOME` is honored)
+//! 4. built-in defaults
+//!
+//! Each setting is taken whole from the highest layer that sets it; lists like
+//! `exclude` are not merged across layers.
+
+use anyhow::{Context, Result, anyhow};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, ValueEnum};
+use globset::Glob;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::Cli;
+use crate::filter::parse_glob;
+use crate::groups::Group;
+use crate::printer::OutputFormat;
+use crate::repo::{ScanDepth, parse_scan_depth};
+use crate::runner::{UrlScheme, format_duration, parse_duration, parse_host_limit};
+use crate::scan::DEFAULT_PRUNE;
+use crate::url_rewrite::{RewriteHost, parse_url_host};
+
+/// Per-workspace config file, looked up from the working directory upwards
+pub const WORKSPACE_CONFIG_FILE: &str = ".git-all.toml";
+
+const DEFAULT_WORKERS: usize = 8;
+const DEFAULT_SCAN_DEPTH: ScanDepth = ScanDepth::Depth(1);
+/// Below OpenSSH's default `MaxSessions` of 10
+const DEFAULT_SSH_MASTER_SESSIONS: usize = 8;
+
+/// Where an effective setting came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    BuiltIn,
+    User(PathBuf),
+    Workspace(PathBuf),
+    CommandLine,
+    Env(String),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::BuiltIn => write!(f, "built-in"),
+            Source::User(path) => write!(f, "user config {}", path.display()),
+            Source::Workspace(path) => write!(f, "workspace config {}", path.display()),
+            Source::CommandLine => write!(f, "command line"),
+            Source::Env(name) => write!(f, "environment {name}"),
+        }
+    }
+}
+
+/// An effective value and the layer that supplied it
+#[derive(Debug, Clone)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: Source,
+}
+
+/// Keys accepted in `config.toml` and `.git-all.toml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct FileConfig {
+    workers: Option<usize>,
+    scan_depth: Option<ScanDepthValue>,
+    url_scheme: Option<String>,
+    url_hosts: Option<Vec<String>>,
+    ssh_multiplexing: Option<bool>,
+    ssh_masters: Option<usize>,
+    ssh_master_sessions: Option<usize>,
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    timeout: Option<String>,
+    retries: Option<u32>,
+    host_workers: Option<BTreeMap<String, usize>>,
+    format: Option<String>,
+    prune: Option<Vec<String>>,
+    discovery_cache: Option<bool>,
+    groups: Option<BTreeMap<String, Vec<String>>>,
+}
+
+/// `scan-depth = 3` or `scan-depth = "all"`
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ScanDepthValue {
+    Depth(usize),
+    Named(String),
+}
+
+impl FileConfig {
+    fn parse(contents: &str) -> Result<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    fn scan_depth(&self) -> Result<Option<ScanDepth>> {
+        self.scan_depth
+            .as_ref()
+            .map(|value| match value {
+                ScanDepthValue::Depth(depth) => parse_scan_depth(&depth.to_string()),
+                ScanDepthValue::Named(name) => parse_scan_depth(name),
+            })
+            .transpose()
+            .map_err(|e| anyhow!("scan-depth: {e}"))
+    }
+
+    fn url_scheme(&self) -> Result<Option<Option<UrlScheme>>> {
+        self.url_scheme
+            .as_deref()
+            .map(|scheme| match scheme.to_ascii_lowercase().as_str() {
+                "ssh" => Ok(Some(UrlScheme::Ssh)),
+                "https" => Ok(Some(UrlScheme::Https)),
+                "auto" => Ok(None),
+                _ => Err(anyhow!(
+                    "url-scheme: expected \"ssh\", \"https\" or \"auto\", got {scheme:?}"
+                )),
+            })
+            .transpose()
+    }
+
+    fn url_hosts(&self) -> Result<Option<Vec<RewriteHost>>> {
+        self.url_hosts
+            .as_ref()
+            .map(|hosts| {
+                hosts
+                    .iter()
+                    .map(|host| parse_url_host(host).map_err(|e| anyhow!("url-hosts: {e}")))
+                    .collect()
+            })
+            .transpose()
+    }
+
+    fn ssh_master_sessions(&self) -> Result<Option<usize>> {
+        match self.ssh_master_sessions {
+            Some(0) => Err(anyhow!("ssh-master-sessions: must be at least 1")),
+            sessions => Ok(sessions),
+        }
+    }
+
+    fn globs(patterns: &Option<Vec<String>>, key: &str) -> Result<Option<Vec<Glob>>> {
+        patterns
+            .as_ref()
+            .map(|patterns| {
+                patterns
+                    .iter()
+                    .map(|pattern| parse_glob(pattern).map_err(|e| anyhow!("{key}: {e}")))
+                    .collect()
+            })
+            .transpose()
+    }
+
+    fn timeout(&self) -> Result<Option<Option<Duration>>> {
+        self.timeout
+            .as_deref()
+            .map(|timeout| {
+                parse_duration(timeout)
+                    .map(Some)
+                    .map_err(|e| anyhow!("timeout: {e}"))
+            })
+            .transpose()
+    }
+
+    fn host_workers(&self) -> Result<Option<Vec<(String, usize)>>> {
+        self.host_workers
+            .as_ref()
+            .map(|limits| {
+                limits
+                    .iter()
+                    .map(|(host, limit)| {
+                        parse_host_limit(&format!("{host}={limit}"))
+                            .map_err(|e| anyhow!("host-workers: {e}"))
+                    })
+                    .collect()
+            })
+            .transpose()
+    }
+
+    fn format(&self) -> Result<Option<OutputFormat>> {
+        self.format
+            .as_deref()
+            .map(|format| OutputFormat::from_str(format, true).map_err(|e| anyhow!("format: {e}")))
+            .transpose()
+    }
+}
+
+/// Config file paths and their parsed contents, when present
+#[derive(Debug, Default)]
+pub struct ConfigFiles {
+    pub user_path: Option<PathBuf>,
+    pub workspace_path: Option<PathBuf>,
+    user: Option<FileConfig>,
+    workspace: Option<FileConfig>,
+}
+
+impl ConfigFiles {
+    /// Load the user config and the nearest workspace config above `cwd`
+    pub fn load(cwd: &Path) -> Result<Self> {
+        let user_path = user_config_path();
+        let workspace_path = find_workspace_config(cwd);
+        Ok(ConfigFiles {
+            user: user_path.as_deref().map(read_config).transpose()?.flatten(),
+            workspace: workspace_path
+                .as_deref()
+                .map(read_config)
+                .transpose()?
+                .flatten(),
+            user_path,
+            workspace_path,
+        })
+    }
+
+    /// Named groups from both files. A workspace group replaces a user group
+    /// of the same name. Relative members are resolved against the workspace
+    /// config's directory, or the home directory for the user config.
+    pub fn groups(&self) -> Vec<Group> {
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        let user = self
+            .user
+            .as_ref()
+            .zip(self.user_path.as_ref())
+            .map(|(config, path)| (config, Source::User(path.clone()), home.clone()));
+        let workspace = self
+            .workspace
+            .as_ref()
+            .zip(self.workspace_path.as_ref())
+            .map(|(config, path)| {
+                let base = path.parent().map(Path::to_path_buf);
+                (config, Source::Workspace(path.clone()), base)
+            });
+
+        let mut groups: BTreeMap<String, Group> = BTreeMap::new();
+        for (config, source, base) in user.into_iter().chain(workspace) {
+            for (name, members) in config.groups.iter().flatten() {
+                let group = Group {
+                    name: name.clone(),
+                    members: members.clone(),
+                    base: base.clone().unwrap_or_default(),
+                    source: source.clone(),
+                };
+                groups.insert(name.clone(), group);
+            }
+        }
+        groups.into_values().collect()
+    }
+
+    /// Take a value from the workspace config, else the user config
+    fn lookup<T>(
+        &self,
+        get: impl Fn(&FileConfig) -> Result<Option<T>>,
+    ) -> Result<Option<Setting<T>>> {
+        let layers = [
+            (
+                &self.workspace,
+                &self.workspace_path,
+                Source::Workspace as fn(PathBuf) -> Source,
+            ),
+            (
+                &self.user,
+                &self.user_path,
+                Source::User as fn(PathBuf) -> Source,
+            ),
+        ];
+        for (config, path, source) in layers {
+            let (Some(config), Some(path)) = (config, path) else {
+                continue;
+            };
+            if let Some(value) = get(config).with_context(|| format!("in {}", path.display()))? {
+                return Ok(Some(Setting {
+                    value,
+                    source: source(path.clone()),
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// `~/rest` under `$HOME`; `None` for other entries
+pub fn expand_home(entry: &str) -> Option<PathBuf> {
+    let rest = entry.strip_prefix("~/")?;
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(rest))
+}
+
+/// `$XDG_CONFIG_HOME/git-all`, falling back to `~/.config/git-all`
+pub fn user_config_dir() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("git-all"))
+}
+
+/// `$XDG_CACHE_HOME/git-all`, falling back to `~/.cache/git-all`
+pub fn user_cache_dir() -> Option<PathBuf> {
+    let cache_home = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(cache_home.join("git-all"))
+}
+
+fn user_config_path() -> Option<PathBuf> {
+    user_config_dir().map(|dir| dir.join("config.toml"))
+}
+
+fn find_workspace_config(cwd: &Path) -> Option<PathBuf> {
+    cwd.ancestors()
+        .map(|dir| dir.join(WORKSPACE_CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+fn read_config(path: &Path) -> Result<Option<FileConfig>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+    FileConfig::parse(&contents)
+        .map(Some)
+        .with_context(|| format!("invalid config file {}", path.display()))
+}
+
+/// Effective options after merging the command line with config files
+#[derive(Debug)]
+pub struct Settings {
+    pub workers: Setting<usize>,
+    pub scan_depth: Setting<ScanDepth>,
+    pub url_scheme: Setting<Option<UrlScheme>>,
+    pub url_hosts: Setting<Vec<RewriteHost>>,
+    pub ssh_multiplexing: Setting<bool>,
+    /// Pre-warmed masters per SSH host; 0 turns the pool off
+    pub ssh_masters: Setting<usize>,
+    pub ssh_master_sessions: Setting<usize>,
+    pub include: Setting<Vec<Glob>>,
+    pub exclude: Setting<Vec<Glob>>,
+    pub timeout: Setting<Option<Duration>>,
+    pub retries: Setting<u32>,
+    pub host_workers: Setting<Vec<(String, usize)>>,
+    pub format: Setting<OutputFormat>,
+    pub prune: Setting<Vec<String>>,
+    pub discovery_cache: Setting<bool>,
+}
+
+impl Settings {
+    pub fn resolve(cli: &Cli, matches: &ArgMatches, files: &ConfigFiles) -> Result<Self> {
+        macro_rules! layered {
+            ($cli:expr, $file:expr, $default:expr) => {
+                match $cli {
+                    Some(setting) => setting,
+                    None => files
+                        .lookup($file)?
+                        .unwrap_or_else(|| Setting::built_in($default)),
+                }
+            };
+        }
+
+        let url_scheme_flag = if cli.ssh {
+            Some(UrlScheme::Ssh)
+        } else if cli.https {
+            Some(UrlScheme::Https)
+        } else {
+            None
+        };
+        let multiplexing_given =
+            given(matches, "ssh_multiplexing").or_else(|| given(matches, "_no_ssh_multiplexing"));
+        let prune_flag = if cli.no_prune {
+            Some(Setting {
+                value: Vec::new(),
+                source: Source::CommandLine,
+            })
+        } else {
+            from_cli(matches, "prune", cli.prune.clone())
+        };
+        let discovery_cache_given =
+            given(matches, "discovery_cache").or_else(|| given(matches, "_no_discovery_cache"));
+
+        Ok(Settings {
+            workers: layered!(
+                cli.workers
+                    .and_then(|workers| from_cli(matches, "workers", workers)),
+                |config| Ok(config.workers),
+                DEFAULT_WORKERS
+            ),
+            scan_depth: layered!(
+                cli.scan_depth
+                    .and_then(|depth| from_cli(matches, "scan_depth", depth)),
+                FileConfig::scan_depth,
+                DEFAULT_SCAN_DEPTH
+            ),
+            url_scheme: layered!(
+                url_scheme_flag.map(|scheme| Setting {
+                    value: Some(scheme),
+                    source: Source::CommandLine,
+                }),
+                FileConfig::url_scheme,
+                None
+            ),
+            url_hosts: layered!(
+                from_cli(matches, "url_host", cli.url_host.clone()),
+                FileConfig::url_hosts,
+                Vec::new()
+            ),
+            ssh_multiplexing: layered!(
+                multiplexing_given.map(|source| Setting {
+                    value: cli.ssh_multiplexing,
+                    source,
+                }),
+                |config| Ok(config.ssh_multiplexing),
+                false
+            ),
+            ssh_masters: layered!(
+                cli.ssh_masters
+                    .and_then(|masters| from_cli(matches, "ssh_masters", masters)),
+                |config| Ok(config.ssh_masters),
+                0
+            ),
+            ssh_master_sessions: layered!(
+                cli.ssh_master_sessions.and_then(|sessions| from_cli(
+                    matches,
+                    "ssh_master_sessions",
+                    sessions
+                )),
+                FileConfig::ssh_master_sessions,
+                DEFAULT_SSH_MASTER_SESSIONS
+            ),
+            include: layered!(
+                from_cli(matches, "include", cli.include.clone()),
+                |config| FileConfig::globs(&config.include, "include"),
+                Vec::new()
+            ),
+            exclude: layered!(
+                from_cli(matches, "exclude", cli.exclude.clone()),
+                |config| FileConfig::globs(&config.exclude, "exclude"),
+                Vec::new()
+            ),
+            timeout: layered!(
+                from_cli(matches, "timeout", cli.timeout),
+                FileConfig::timeout,
+                None
+            ),
+            retries: layered!(
+                cli.retries
+                    .and_then(|retries| from_cli(matches, "retries", retries)),
+                |config| Ok(config.retries),
+                0
+            ),
+            host_workers: layered!(
+                from_cli(matches, "host_workers", cli.host_workers.clone()),
+                FileConfig::host_workers,
+                Vec::new()
+            ),
+            format: layered!(
+                cli.format
+                    .and_then(|format| from_cli(matches, "format", format)),
+                FileConfig::format,
+                OutputFormat::Text
+            ),
+            prune: layered!(
+                prune_flag,
+                |config| Ok(config.prune.clone()),
+                DEFAULT_PRUNE.iter().map(|name| name.to_string()).collect()
+            ),
+            discovery_cache: layered!(
+                discovery_cache_given.map(|source| Setting {
+                    value: cli.discovery_cache,
+                    source,
+                }),
+                |config| Ok(config.discovery_cache),
+                false
+            ),
+        })
+    }
+
+    /// `(key, value, source)` rows for `git-all meta config`, values in TOML syntax
+    pub fn describe(&self) -> Vec<(&'static str, String, &Source)> {
+        let globs = |globs: &[Glob]| {
+            let quoted: Vec<String> = globs
+                .iter()
+                .map(|glob| format!("{:?}", glob.glob()))
+                .collect();
+            format!("[{}]", quoted.join(", "))
+        };
+        vec![
+            (
+                "workers",
+                self.workers.value.to_string(),
+                &self.workers.source,
+            ),
+            (
+                "scan-depth",
+                match self.scan_depth.value {
+                    ScanDepth::All => "\"all\"".to_string(),
+                    ScanDepth::Depth(depth) => depth.to_string(),
+                },
+                &self.scan_depth.source,
+            ),
+            (
+                "url-scheme",
+                match self.url_scheme.value {
+                    Some(UrlScheme::Ssh) => "\"ssh\"",
+                    Some(UrlScheme::Https) => "\"https\"",
+                    None => "\"auto\"",
+                }
+                .to_string(),
+                &self.url_scheme.source,
+            ),
+            (
+                "url-hosts",
+                format!(
+                    "{:?}",
+                    self.url_hosts
+                        .value
+                        .iter()
+                        .map(RewriteHost::to_string)
+                        .collect::<Vec<_>>()
+                ),
+                &self.url_hosts.source,
+            ),
+            (
+                "ssh-multiplexing",
+                self.ssh_multiplexing.value.to_string(),
+                &self.ssh_multiplexing.source,
+            ),
+            (
+                "ssh-masters",
+                self.ssh_masters.value.to_string(),
+                &self.ssh_masters.source,
+            ),
+            (
+                "ssh-master-sessions",
+                self.ssh_master_sessions.value.to_string(),
+                &self.ssh_master_sessions.source,
+            ),
+            ("include", globs(&self.include.value), &self.include.source),
+            ("exclude", globs(&self.exclude.value), &self.exclude.source),
+            (
+                "timeout",
+                self.timeout.value.map_or("none".to_string(), |timeout| {
+                    format!("\"{}\"", format_duration(timeout))
+                }),
+                &self.timeout.source,
+            ),
+            (
+                "retries",
+                self.retries.value.to_string(),
+                &self.retries.source,
+            ),
+            (
+                "host-workers",
+                format!(
+                    "{{{}}}",
+                    self.host_workers
+                        .value
+                        .iter()
+                        .map(|(host, limit)| format!("{host:?} = {limit}"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                &self.host_workers.source,
+            ),
+            (
+                "format",
+                format!(
+                    "{:?}",
+                    self.format
+                        .value
+                        .to_possible_value()
+                        .map(|value| value.get_name().to_string())
+                        .unwrap_or_default()
+                ),
+                &self.format.source,
+            ),
+            (
+                "prune",
+                format!("{:?}", self.prune.value),
+                &self.prune.source,
+            ),
+            (
+                "discovery-cache",
+                self.discovery_cache.value.to_string(),
+                &self.discovery_cache.source,
+            ),
+        ]
+    }
+}
+
+impl<T> Setting<T> {
+    fn built_in(value: T) -> Self {
+        Setting {
+            value,
+            source: Source::BuiltIn,
+        }
+    }
+}
+
+/// Whether an option was given on the command line or through its env var
+fn given(matches: &ArgMatches, id: &str) -> Option<Source> {
+    match matches.value_source(id)? {
+        ValueSource::CommandLine => Some(Source::CommandLine),
+        ValueSource::EnvVariable => {
+            Some(Source::Env(format!("GIT_ALL_{}", id.to_ascii_uppercase())))
+        }
+        _ => None,
+    }
+}
+
+fn from_cli<T>(matches: &ArgMatches, id: &str, value: T) -> Option<Setting<T>> {
+    given(matches, id).map(|source| Setting { value, source })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, FromArgMatches};
+
+    fn resolve(args: &[&str], files: &ConfigFiles) -> Settings {
+        let matches = Cli::command()
+            .try_get_matches_from(std::iter::once("git-all").chain(args.iter().copied()))
+            .expect("valid args");
+        let cli = Cli::from_arg_matches(&matches).expect("cli");
+        Settings::resolve(&cli, &matches, files).expect("settings")
+    }
+
+    fn files(user: &str, workspace: &str) -> ConfigFiles {
+        ConfigFiles {
+            user_path: Some(PathBuf::from("/home/me/.config/git-all/config.toml")),
+            workspace_path: Some(PathBuf::from("/src/.git-all.toml")),
+            user: Some(FileConfig::parse(user).expect("user config")),
+            workspace: Some(FileConfig::parse(workspace).expect("workspace config")),
+        }
+    }
+
+    #[test]
+    fn test_builtin_defaults() {
+        let settings = resolve(&["status"], &ConfigFiles::default());
+        assert_eq!(settings.workers.value, 8);
+        assert_eq!(settings.workers.source, Source::BuiltIn);
+        assert_eq!(settings.scan_depth.value, ScanDepth::Depth(1));
+        assert!(settings.url_scheme.value.is_none());
+        assert!(!settings.ssh_multiplexing.value);
+        assert_eq!(settings.format.value, OutputFormat::Text);
+        assert!(settings.prune.value.contains(&"node_modules".to_string()));
+        assert!(!settings.discovery_cache.value);
+    }
+
+    #[test]
+    fn test_precedence_cli_over_workspace_over_user() {
+        let files = files(
+            "workers = 4\nscan-depth = \"all\"\nssh-multiplexing = true\nretries = 1\n",
+            "workers = 16\nexclude = [\"archive/*\"]\n",
+        );
+
+        let settings = resolve(&["status"], &files);
+        assert_eq!(settings.workers.value, 16);
+        assert_eq!(
+            settings.workers.source,
+            Source::Workspace(PathBuf::from("/src/.git-all.toml"))
+        );
+        assert_eq!(settings.scan_depth.value, ScanDepth::All);
+        assert!(matches!(settings.scan_depth.source, Source::User(_)));
+        assert!(settings.ssh_multiplexing.value);
+        assert_eq!(settings.exclude.value.len(), 1);
+        assert_eq!(settings.retries.value, 1);
+
+        let settings = resolve(&["-n", "2", "--no-ssh-multiplexing", "status"], &files);
+        assert_eq!(settings.workers.value, 2);
+        assert_eq!(settings.workers.source, Source::CommandLine);
+        assert!(!settings.ssh_multiplexing.value);
+        assert_eq!(settings.ssh_multiplexing.source, Source::CommandLine);
+    }
+
+    #[test]
+    fn test_file_values_are_parsed() {
+        let files = files(
+            "",
+            "url-scheme = \"https\"\ntimeout = \"45s\"\nformat = \"ndjson\"\n\
+             [host-workers]\n\"github.com\" = 10\n",
+        );
+        let settings = resolve(&["status"], &files);
+        assert!(matches!(settings.url_scheme.value, Some(UrlScheme::Https)));
+        assert_eq!(settings.timeout.value, Some(Duration::from_secs(45)));
+        assert_eq!(settings.format.value, OutputFormat::Ndjson);
+        assert_eq!(
+            settings.host_workers.value,
+            vec![("github.com".to_string(), 10)]
+        );
+
+        let settings = resolve(&["--ssh", "status"], &files);
+        assert!(matches!(settings.url_scheme.value, Some(UrlScheme::Ssh)));
+    }
+
+    #[test]
+    fn test_discovery_settings() {
+        let files = files("discovery-cache = true\n", "prune = [\"vendor\"]\n");
+        let settings = resolve(&["status"], &files);
+        assert_eq!(settings.prune.value, vec!["vendor"]);
+        assert!(settings.discovery_cache.value);
+
+        let settings = resolve(&["--no-prune", "--no-discovery-cache", "status"], &files);
+        assert!(settings.prune.value.is_empty());
+        assert!(!settings.discovery_cache.value);
+
+        let settings = resolve(&["--prune", "dist,build", "status"], &files);
+        assert_eq!(settings.prune.value, vec!["dist", "build"]);
+        assert_eq!(settings.prune.source, Source::CommandLine);
+    }
+
+    #[test]
+    fn test_url_hosts_settings() {
+        let files = files("url-hosts = [\"gitea@git.example.com:2222\"]\n", "");
+        let settings = resolve(&["status"], &files);
+        let hosts: Vec<String> = settings
+            .url_hosts
+            .value
+            .iter()
+            .map(|h| h.to_string())
+            .collect();
+        assert_eq!(hosts, vec!["gitea@git.example.com:2222"]);
+
+        let settings = resolve(&["--url-host", "gitlab.internal", "status"], &files);
+        assert_eq!(settings.url_hosts.value[0].host, "gitlab.internal");
+        assert_eq!(settings.url_hosts.source, Source::CommandLine);
+
+        assert!(
+            FileConfig::parse("url-hosts = [\"a:b:c\"]\n")
+                .unwrap()
+                .url_hosts()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_ssh_master_settings() {
+        let files = files("ssh-masters = 4\n", "");
+        let settings = resolve(&["fetch"], &files);
+        assert_eq!(settings.ssh_masters.value, 4);
+        assert_eq!(
+            settings.ssh_master_sessions.value,
+            DEFAULT_SSH_MASTER_SESSIONS
+        );
+
+        let settings = resolve(&["--ssh-master-sessions", "3", "fetch"], &files);
+        assert_eq!(settings.ssh_master_sessions.value, 3);
+        assert_eq!(settings.ssh_master_sessions.source, Source::CommandLine);
+
+        assert!(
+            FileConfig::parse("ssh-master-sessions = 0\n")
+                .unwrap()
+                .ssh_master_sessions()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_invalid_values_name_the_file() {
+        let files = files("", "url-scheme = \"ftp\"\n");
+        let matches = Cli::command()
+            .try_get_matches_from(["git-all", "status"])
+            .unwrap();
+        let cli = Cli::from_arg_matches(&matches).unwrap();
+        let err = Settings::resolve(&cli, &matches, &files).unwrap_err();
+        let message = format!("{err:#}");
+        assert!(message.contains("/src/.git-all.toml"), "{message}");
+        assert!(message.contains("url-scheme"), "{message}");
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        assert!(FileConfig::parse("wokers = 4\n").is_err());
+    }
+
+    #[test]
+    fn test_find_workspace_config_walks_up() {
+        let temp = tempfile::tempdir().unwrap();
+        let nested = temp.path().join("a/b");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_workspace_config(&nested), None);
+
+        fs::write(temp.path().join(WORKSPACE_CONFIG_FILE), "workers = 3\n").unwrap();
+        assert_eq!(
+            find_workspace_config(&nested),
+            Some(temp.path().join(WORKSPACE_CONFIG_FILE))
+        );
+    }
+}
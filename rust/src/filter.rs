@@ -0,0 +1,168 @@
+//! Selecting a subset of discovered repositories by path.
+//!
+//! Patterns are globs matched against a repo's path relative to the scan root
+//! (its display name). A pattern that matches a parent directory matches
+//! everything below it, so `archive` and `archive/*` both cover
+//! `archive/2019/old-repo`.
+
+use anyhow::{Context, Result};
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use std::fs;
+use std::path::Path;
+
+/// Per-directory file listing paths for discovery to skip, one glob per line
+pub const IGNORE_FILE: &str = ".git-all-ignore";
+
+/// Parse one `--include` / `--exclude` glob
+pub fn parse_glob(value: &str) -> Result<Glob, String> {
+    build_glob(value).map_err(|e| e.to_string())
+}
+
+fn build_glob(pattern: &str) -> Result<Glob, globset::Error> {
+    // `*` stays within one path component; `**` crosses them
+    GlobBuilder::new(pattern.trim_matches('/'))
+        .literal_separator(true)
+        .build()
+}
+
+/// A set of globs matched against a relative path or any of its ancestors
+#[derive(Clone, Debug)]
+pub struct PathGlobs(GlobSet);
+
+impl PathGlobs {
+    pub fn new(globs: &[Glob]) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for glob in globs {
+            builder.add(glob.clone());
+        }
+        Ok(PathGlobs(builder.build()?))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// True when `path` or one of its parent directories matches a pattern
+    pub fn matches(&self, path: &Path) -> bool {
+        path.ancestors()
+            .filter(|ancestor| !ancestor.as_os_str().is_empty())
+            .any(|ancestor| self.0.is_match(ancestor))
+    }
+
+    /// Load `.git-all-ignore` from `dir`. Blank lines and `#` comments are skipped.
+    pub fn load_ignore_file(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(IGNORE_FILE);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to read {}", path.display()));
+            }
+        };
+
+        let globs = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                build_glob(line)
+                    .with_context(|| format!("invalid pattern {line:?} in {}", path.display()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Some(PathGlobs::new(&globs)?))
+    }
+}
+
+/// `--include` / `--exclude` selection applied to discovered repos
+#[derive(Clone, Debug)]
+pub struct RepoFilter {
+    include: PathGlobs,
+    exclude: PathGlobs,
+}
+
+impl RepoFilter {
+    pub fn new(include: &[Glob], exclude: &[Glob]) -> Result<Self> {
+        Ok(RepoFilter {
+            include: PathGlobs::new(include)?,
+            exclude: PathGlobs::new(exclude)?,
+        })
+    }
+
+    /// Whether any include or exclude pattern was given
+    pub fn is_active(&self) -> bool {
+        !self.include.is_empty() || !self.exclude.is_empty()
+    }
+
+    /// Keep a repo when it matches an include (or there are none) and no exclude
+    pub fn allows(&self, display_path: &str) -> bool {
+        let path = Path::new(display_path);
+        (self.include.is_empty() || self.include.matches(path)) && !self.exclude.matches(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn globs(patterns: &[&str]) -> Vec<Glob> {
+        patterns
+            .iter()
+            .map(|pattern| parse_glob(pattern).expect("valid glob"))
+            .collect()
+    }
+
+    #[test]
+    fn test_exclude_matches_directory_and_descendants() {
+        let filter = RepoFilter::new(&[], &globs(&["archive/*"])).unwrap();
+        assert!(!filter.allows("archive/old"));
+        assert!(!filter.allows("archive/2019/older"));
+        assert!(filter.allows("archived"));
+        assert!(filter.allows("work/archive"));
+
+        let filter = RepoFilter::new(&[], &globs(&["archive"])).unwrap();
+        assert!(!filter.allows("archive/old"));
+        assert!(filter.allows("work/archive-tools"));
+    }
+
+    #[test]
+    fn test_star_does_not_cross_separators() {
+        let filter = RepoFilter::new(&globs(&["*-api"]), &[]).unwrap();
+        assert!(filter.allows("billing-api"));
+        assert!(!filter.allows("services/billing-api"));
+
+        let filter = RepoFilter::new(&globs(&["**/*-api"]), &[]).unwrap();
+        assert!(filter.allows("services/billing-api"));
+    }
+
+    #[test]
+    fn test_include_and_exclude_combine() {
+        let filter = RepoFilter::new(&globs(&["work/*"]), &globs(&["work/legacy-*"])).unwrap();
+        assert!(filter.is_active());
+        assert!(filter.allows("work/app"));
+        assert!(!filter.allows("work/legacy-app"));
+        assert!(!filter.allows("personal/app"));
+    }
+
+    #[test]
+    fn test_no_patterns_allows_everything() {
+        let filter = RepoFilter::new(&[], &[]).unwrap();
+        assert!(!filter.is_active());
+        assert!(filter.allows("anything/at/all"));
+    }
+
+    #[test]
+    fn test_load_ignore_file() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        assert!(PathGlobs::load_ignore_file(temp.path()).unwrap().is_none());
+
+        fs::write(
+            temp.path().join(IGNORE_FILE),
+            "# old stuff\n\narchive/\nscratch-*\n",
+        )
+        .unwrap();
+        let ignore = PathGlobs::load_ignore_file(temp.path()).unwrap().unwrap();
+        assert!(ignore.matches(Path::new("archive")));
+        assert!(ignore.matches(Path::new("scratch-1")));
+        assert!(!ignore.matches(Path::new("project")));
+    }
+}
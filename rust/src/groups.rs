@@ -0,0 +1,306 @@
+//! Named repository groups from config, selected with `--group`.
+//!
+//! ```toml
+//! [groups]
+//! backend = ["services/api", "services/*-worker", "~/work/billing"]
+//! ```
+//!
+//! Members are repo paths or globs. `--group a --group b` runs the union of
+//! both groups instead of discovering repos; `--group '!legacy'` removes a
+//! group's repos from whatever would otherwise run.
+
+use anyhow::{Result, bail};
+use globset::GlobBuilder;
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::{Source, expand_home};
+use crate::repo::{ScanDepth, find_git_repos_in};
+
+/// A named group as defined in one config file
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub name: String,
+    /// Paths or globs as written in the config
+    pub members: Vec<String>,
+    /// Directory relative members are resolved against
+    pub base: PathBuf,
+    pub source: Source,
+}
+
+/// What one member entry resolved to
+#[derive(Debug, PartialEq, Eq)]
+pub enum Resolved {
+    Repos(Vec<PathBuf>),
+    Missing(PathBuf),
+    NotARepo(PathBuf),
+    NoMatches,
+}
+
+impl Group {
+    /// Resolve each member entry, in config order
+    pub fn resolve(&self) -> Vec<(&str, Resolved)> {
+        self.members
+            .iter()
+            .map(|entry| (entry.as_str(), resolve_member(&self.base, entry)))
+            .collect()
+    }
+
+    /// Every repo in the group. Problem members are reported on stderr.
+    fn repos(&self) -> Vec<PathBuf> {
+        let mut repos = Vec::new();
+        for (entry, resolved) in self.resolve() {
+            match resolved {
+                Resolved::Repos(found) => repos.extend(found),
+                problem => eprintln!(
+                    "git-all: group {}: skipping {entry} ({})",
+                    self.name,
+                    problem.describe()
+                ),
+            }
+        }
+        repos
+    }
+}
+
+impl Resolved {
+    /// Short explanation for members that resolved to no repos
+    pub fn describe(&self) -> String {
+        match self {
+            Resolved::Repos(repos) if repos.is_empty() => "no repositories".to_string(),
+            Resolved::Repos(_) => "ok".to_string(),
+            Resolved::Missing(path) => format!("{} does not exist", path.display()),
+            Resolved::NotARepo(path) => format!("{} is not a git repository", path.display()),
+            Resolved::NoMatches => "glob matched no repositories".to_string(),
+        }
+    }
+}
+
+fn resolve_member(base: &Path, entry: &str) -> Resolved {
+    let path = expand_home(entry)
+        .map(|path| base.join(path))
+        .unwrap_or_else(|| base.join(entry));
+
+    if is_glob(entry) {
+        let repos = expand_glob(&path);
+        return if repos.is_empty() {
+            Resolved::NoMatches
+        } else {
+            Resolved::Repos(repos)
+        };
+    }
+
+    if !path.exists() {
+        return Resolved::Missing(path);
+    }
+    if !path.join(".git").exists() {
+        return Resolved::NotARepo(path);
+    }
+    Resolved::Repos(vec![path.canonicalize().unwrap_or(path)])
+}
+
+fn is_glob(entry: &str) -> bool {
+    entry.contains(['*', '?', '[', '{'])
+}
+
+/// Repos matching an absolute glob. Discovery starts at the glob's literal
+/// prefix and goes only as deep as the pattern can match.
+fn expand_glob(pattern: &Path) -> Vec<PathBuf> {
+    let Ok(glob) = GlobBuilder::new(&pattern.to_string_lossy())
+        .literal_separator(true)
+        .build()
+    else {
+        return Vec::new();
+    };
+    let matcher = glob.compile_matcher();
+
+    let components: Vec<Component> = pattern.components().collect();
+    let literal = components
+        .iter()
+        .take_while(|component| !is_glob(&component.as_os_str().to_string_lossy()))
+        .count();
+    let base: PathBuf = components[..literal].iter().collect();
+    let rest = &components[literal..];
+    let depth = if rest.iter().any(|component| component.as_os_str() == "**") {
+        ScanDepth::All
+    } else {
+        ScanDepth::Depth(rest.len())
+    };
+
+    let Ok(found) = find_git_repos_in(&base, depth) else {
+        return Vec::new();
+    };
+    found
+        .into_iter()
+        .filter(|repo| matcher.is_match(repo))
+        .map(|repo| repo.canonicalize().unwrap_or(repo))
+        .collect()
+}
+
+/// `--group` values split into groups to run and groups to leave out
+#[derive(Debug, Default)]
+pub struct GroupFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl GroupFilter {
+    pub fn new(specs: &[String]) -> Self {
+        let mut filter = GroupFilter::default();
+        for spec in specs.iter().map(|spec| spec.trim()) {
+            match spec.strip_prefix('!') {
+                Some(name) => filter.exclude.push(name.to_string()),
+                None => filter.include.push(spec.to_string()),
+            }
+        }
+        filter
+    }
+
+    /// Whether the included groups replace directory discovery
+    pub fn replaces_discovery(&self) -> bool {
+        !self.include.is_empty()
+    }
+
+    /// Union of the included groups' repos, sorted
+    pub fn included(&self, groups: &[Group]) -> Result<Vec<PathBuf>> {
+        let mut repos = BTreeSet::new();
+        for group in find_groups(groups, &self.include)? {
+            repos.extend(group.repos());
+        }
+        Ok(repos.into_iter().collect())
+    }
+
+    /// Drop repos belonging to any excluded group
+    pub fn remove_excluded(&self, groups: &[Group], repos: &mut Vec<PathBuf>) -> Result<()> {
+        if self.exclude.is_empty() {
+            return Ok(());
+        }
+        let excluded: HashSet<PathBuf> = find_groups(groups, &self.exclude)?
+            .into_iter()
+            .flat_map(Group::repos)
+            .collect();
+        repos.retain(|repo| {
+            let canonical = repo.canonicalize().unwrap_or_else(|_| repo.clone());
+            !excluded.contains(&canonical)
+        });
+        Ok(())
+    }
+}
+
+fn find_groups<'a>(groups: &'a [Group], names: &[String]) -> Result<Vec<&'a Group>> {
+    names
+        .iter()
+        .map(|name| {
+            let Some(group) = groups.iter().find(|group| &group.name == name) else {
+                let known: Vec<&str> = groups.iter().map(|group| group.name.as_str()).collect();
+                if known.is_empty() {
+                    bail!("unknown group {name:?}: no groups are defined in config");
+                }
+                bail!("unknown group {name:?} (defined: {})", known.join(", "));
+            };
+            Ok(group)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn make_repo(path: &Path) {
+        fs::create_dir_all(path.join(".git")).unwrap();
+    }
+
+    fn group(name: &str, base: &Path, members: &[&str]) -> Group {
+        Group {
+            name: name.to_string(),
+            members: members.iter().map(|member| member.to_string()).collect(),
+            base: base.to_path_buf(),
+            source: Source::BuiltIn,
+        }
+    }
+
+    #[test]
+    fn test_resolve_paths_and_globs() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        make_repo(&root.join("services/api"));
+        make_repo(&root.join("services/billing-worker"));
+        make_repo(&root.join("services/email-worker"));
+        make_repo(&root.join("services/nested/deep-worker"));
+        fs::create_dir_all(root.join("docs")).unwrap();
+
+        let backend = group(
+            "backend",
+            &root,
+            &[
+                "services/api",
+                "services/*-worker",
+                "docs",
+                "gone",
+                "infra/*",
+            ],
+        );
+        let resolved = backend.resolve();
+        assert_eq!(
+            resolved[0].1,
+            Resolved::Repos(vec![root.join("services/api")])
+        );
+        assert_eq!(
+            resolved[1].1,
+            Resolved::Repos(vec![
+                root.join("services/billing-worker"),
+                root.join("services/email-worker"),
+            ])
+        );
+        assert_eq!(resolved[2].1, Resolved::NotARepo(root.join("docs")));
+        assert_eq!(resolved[3].1, Resolved::Missing(root.join("gone")));
+        assert_eq!(resolved[4].1, Resolved::NoMatches);
+
+        let all = group("all", &root, &["**/*-worker"]);
+        let Resolved::Repos(repos) = &all.resolve()[0].1 else {
+            panic!("expected repos");
+        };
+        assert!(repos.contains(&root.join("services/nested/deep-worker")));
+    }
+
+    #[test]
+    fn test_group_filter_union_and_exclusion() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        for name in ["api", "web", "legacy"] {
+            make_repo(&root.join(name));
+        }
+        let groups = vec![
+            group("backend", &root, &["api", "legacy"]),
+            group("frontend", &root, &["web"]),
+            group("old", &root, &["legacy"]),
+        ];
+
+        let filter = GroupFilter::new(&["backend".to_string(), "frontend".to_string()]);
+        assert!(filter.replaces_discovery());
+        let mut repos = filter.included(&groups).unwrap();
+        assert_eq!(
+            repos,
+            vec![root.join("api"), root.join("legacy"), root.join("web")]
+        );
+
+        let filter = GroupFilter::new(&["!old".to_string()]);
+        assert!(!filter.replaces_discovery());
+        filter.remove_excluded(&groups, &mut repos).unwrap();
+        assert_eq!(repos, vec![root.join("api"), root.join("web")]);
+    }
+
+    #[test]
+    fn test_unknown_group_lists_defined_groups() {
+        let groups = vec![group("backend", Path::new("/"), &[])];
+        let err = GroupFilter::new(&["mobile".to_string()])
+            .included(&groups)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown group \"mobile\" (defined: backend)"
+        );
+    }
+}
@@ -1,21 +1,61 @@
-use anyhow::Result;
-use clap::{Parser, Subcommand};
-use std::process::Command;
-use std::time::Instant;
+use anyhow::{Result, bail};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use globset::Glob;
+use std::path::PathBuf;
+use std::process::{Command, ExitCode};
+use std::time::{Duration, Instant};
 
 #[cfg(unix)]
 use std::os::unix::process::CommandExt;
 
+mod cancel;
+mod clone_manifest;
 mod commands;
+mod config;
+mod filter;
+mod groups;
+mod manifest;
 mod meta;
+mod printer;
 mod repo;
+mod retry;
+mod roots;
 mod runner;
+mod scan;
+mod select;
+mod ssh_pool;
 mod trace;
+mod url_rewrite;
 
-use commands::{fetch, passthrough, pull, status};
-use repo::{ScanDepth, find_git_repos_in, is_inside_git_repo, parse_scan_depth};
-use runner::{ExecutionContext, UrlScheme};
+use commands::{clone, fetch, passthrough, pull, push, status, sync};
+use config::{ConfigFiles, Settings, user_cache_dir};
+use filter::{RepoFilter, parse_glob};
+use groups::GroupFilter;
+use printer::OutputFormat;
+use repo::{
+    DiscoveryOptions, ScanDepth, WorktreeMode, discover_repos, is_inside_git_repo,
+    parse_scan_depth, repo_display_name,
+};
+use retry::RetryPolicy;
+use roots::{Registry, owning_root};
+use runner::{
+    ExecutionContext, GitInvocationOptions, WorkerLimits, failure_summary, interruption_summary,
+    parse_duration, parse_host_limit,
+};
+use select::{Excluded, Selection, parse_branch_glob};
+use ssh_pool::PoolSize;
 use trace::TraceSink;
+use url_rewrite::{RewriteHost, parse_url_host};
+
+/// Exit code when one or more repositories failed (SPEC 8.2)
+const EXIT_REPO_FAILURE: u8 = 1;
+/// Exit code for git-all-level failures, including empty discovery, rejected
+/// command lines and errors from `run` (SPEC 2.2, 8.3)
+const EXIT_GIT_ALL_FAILURE: u8 = 9;
+/// Exit code after Ctrl-C, matching the shell convention of 128 + SIGINT
+const EXIT_INTERRUPTED: u8 = 130;
+/// Operating mode override: `auto` (the default) or `multi`
+const MODE_ENV: &str = "GIT_ALL_MODE";
 
 #[derive(Parser)]
 #[command(
@@ -28,14 +68,26 @@ struct Cli {
     #[arg(long)]
     dry_run: bool,
 
-    /// Force SSH URLs (git@github.com:) for all remotes
+    /// Run across repositories even from inside one, instead of passing through to git (env: GIT_ALL_MODE=multi)
+    #[arg(long)]
+    all: bool,
+
+    /// Discover repositories under DIR instead of the current directory
+    #[arg(long, value_name = "DIR")]
+    root: Option<PathBuf>,
+
+    /// Force SSH URLs (git@host:) for all remotes
     #[arg(long, conflicts_with = "https")]
     ssh: bool,
 
-    /// Force HTTPS URLs (https://github.com/) for all remotes
+    /// Force HTTPS URLs (https://host/) for all remotes
     #[arg(long, conflicts_with = "ssh")]
     https: bool,
 
+    /// Extra host for --ssh/--https rewrites, as [USER@]HOST[:SSH-PORT]; repeatable
+    #[arg(long, value_delimiter = ',', value_parser = parse_url_host, value_name = "HOST")]
+    url_host: Vec<RewriteHost>,
+
     /// Enable SSH ControlMaster connection multiplexing (off by default)
     #[arg(long, overrides_with = "_no_ssh_multiplexing")]
     ssh_multiplexing: bool,
@@ -47,13 +99,133 @@ struct Cli {
     )]
     _no_ssh_multiplexing: bool,
 
+    /// Open K SSH master connections per host before network commands and spread repos across them (0 = off)
+    #[arg(long, value_name = "K")]
+    ssh_masters: Option<usize>,
+
+    /// Git sessions each pre-warmed SSH master carries at once (default: 8)
+    #[arg(long, value_name = "N", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    ssh_master_sessions: Option<usize>,
+
     /// Number of parallel workers (default: 8, 0 = unlimited)
-    #[arg(short = 'n', long, default_value = "8")]
-    workers: usize,
+    #[arg(short = 'n', long)]
+    workers: Option<usize>,
+
+    /// Per-host limits for network commands, e.g. github.com=10,gitlab.internal=4
+    #[arg(long, value_delimiter = ',', value_parser = parse_host_limit, value_name = "HOST=N")]
+    host_workers: Vec<(String, usize)>,
+
+    /// How deep to scan for repositories (positive integer or "all", default: 1)
+    #[arg(long, value_parser = parse_scan_depth, value_name = "DEPTH|all")]
+    scan_depth: Option<ScanDepth>,
+
+    /// Also discover bare repositories (e.g. foo.git mirrors)
+    #[arg(long)]
+    include_bare: bool,
+
+    /// How to list linked worktrees: one row per repo, a row each after their main repo, or none
+    #[arg(long, value_enum, default_value_t = WorktreeMode::Separate, value_name = "MODE")]
+    worktrees: WorktreeMode,
+
+    /// Also list initialized submodules, under their parent repo
+    #[arg(long)]
+    recurse_submodules: bool,
+
+    /// Follow symlinked directories while scanning, skipping loops and
+    /// duplicates (on by default)
+    #[arg(long = "follow-symlinks", overrides_with = "no_follow_symlinks")]
+    _follow_symlinks: bool,
+
+    /// Skip symlinked directories while scanning instead of following them
+    #[arg(long, overrides_with = "_follow_symlinks")]
+    no_follow_symlinks: bool,
+
+    /// Don't scan into directories on other file systems, such as network mounts
+    #[arg(long)]
+    one_file_system: bool,
+
+    /// Directory names never scanned, replacing the default list (node_modules, target, ...); repeatable
+    #[arg(long, value_delimiter = ',', value_name = "NAME")]
+    prune: Vec<String>,
+
+    /// Scan every directory, including node_modules, target, ...
+    #[arg(long, conflicts_with = "prune")]
+    no_prune: bool,
+
+    /// Cache discovered repos and reuse them until a scanned directory changes
+    #[arg(long, overrides_with = "_no_discovery_cache")]
+    discovery_cache: bool,
+
+    #[arg(
+        long = "no-discovery-cache",
+        overrides_with = "discovery_cache",
+        hide = true
+    )]
+    _no_discovery_cache: bool,
+
+    /// Read repo paths from FILE (or - for stdin): a path per line, a repo manifest, .mrconfig or .gitmodules
+    #[arg(long, value_name = "FILE|-", conflicts_with = "group")]
+    repos_from: Option<String>,
+
+    /// Run in the repos of this config group instead of scanning; repeatable, '!NAME' excludes
+    #[arg(long, value_delimiter = ',', value_name = "NAME")]
+    group: Vec<String>,
+
+    /// Only run in repos whose relative path matches this glob; repeatable
+    #[arg(long, value_parser = parse_glob, value_name = "GLOB")]
+    include: Vec<Glob>,
 
-    /// How deep to scan for repositories (positive integer or "all")
-    #[arg(long, default_value = "1", value_parser = parse_scan_depth, value_name = "DEPTH|all")]
-    scan_depth: ScanDepth,
+    /// Skip repos whose relative path matches this glob, e.g. 'archive/*'; repeatable
+    #[arg(long, value_parser = parse_glob, value_name = "GLOB")]
+    exclude: Vec<Glob>,
+
+    /// Only run in repos with modified, staged or untracked files
+    #[arg(long, conflicts_with = "only_clean")]
+    only_dirty: bool,
+
+    /// Only run in repos with a clean worktree
+    #[arg(long)]
+    only_clean: bool,
+
+    /// Only run in repos whose branch is behind its upstream (as of the last fetch)
+    #[arg(long)]
+    only_behind: bool,
+
+    /// Only run in repos whose branch has commits its upstream doesn't (nothing to push otherwise)
+    #[arg(long)]
+    only_ahead: bool,
+
+    /// Only run in repos whose current branch matches this glob
+    #[arg(long, value_parser = parse_branch_glob, value_name = "GLOB")]
+    on_branch: Option<Glob>,
+
+    /// Skip repos whose current branch matches this glob
+    #[arg(long, value_parser = parse_branch_glob, value_name = "GLOB")]
+    not_on_branch: Option<Glob>,
+
+    /// Only run in repos that have a remote with this name
+    #[arg(long, value_name = "NAME")]
+    has_remote: Option<String>,
+
+    /// Only run in repos with a remote on this host
+    #[arg(long, value_name = "HOST")]
+    remote_host: Option<String>,
+
+    /// Kill a repo's git command after this long, e.g. 30s, 500ms, 2m
+    #[arg(long, env = "GIT_ALL_TIMEOUT", value_parser = parse_duration, value_name = "DURATION")]
+    timeout: Option<Duration>,
+
+    /// Re-run a repo's git command up to N times when it fails with a transient network error
+    #[arg(long, env = "GIT_ALL_RETRIES", value_name = "N")]
+    retries: Option<u32>,
+
+    /// Extra stderr text (case-insensitive) that marks a failure as transient; repeatable
+    #[arg(long = "retry-on", value_name = "PATTERN")]
+    retry_on: Vec<String>,
+
+    /// Output format: human-readable text (default), a JSON array, or streaming NDJSON
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
 
     #[command(subcommand)]
     command: Option<Commands>,
@@ -67,35 +239,74 @@ enum Commands {
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         args: Vec<String>,
     },
+    /// Push all repositories
+    Push {
+        /// Additional arguments to pass to git push
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
     /// Fetch all repositories
     Fetch {
         /// Additional arguments to pass to git fetch
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         args: Vec<String>,
     },
+    /// Fetch, then fast-forward branches that are clean and strictly behind
+    Sync {
+        /// Additional arguments to pass to git fetch
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Clone the repositories listed in a manifest that aren't checked out yet
+    Clone {
+        /// Manifest file (TOML, YAML or one URL per line), or - for stdin
+        manifest: String,
+        /// Additional arguments to pass to git clone
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
     /// Status of all repositories
     Status {
         /// Additional arguments to pass to git status
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         args: Vec<String>,
     },
-    /// git-all internal commands (help, version info)
+    /// git-all internal commands (help, effective config, groups)
     Meta {
-        /// Subcommand (help is the only option)
+        /// Subcommand: help, config or groups
         #[arg(trailing_var_arg = true)]
         args: Vec<String>,
     },
+    /// Manage the roots that git-all-roots runs across (default: list)
+    Roots {
+        #[command(subcommand)]
+        action: Option<RootsAction>,
+    },
     /// Pass through to git (any other command)
     #[command(external_subcommand)]
     External(Vec<String>),
 }
 
+#[derive(Subcommand)]
+enum RootsAction {
+    /// List registered roots
+    List,
+    /// Register a directory as a root
+    Add { path: PathBuf },
+    /// Unregister a root
+    Rm { path: PathBuf },
+}
+
 fn command_label(command: &Option<Commands>) -> String {
     match command {
         Some(Commands::Pull { .. }) => "pull".to_string(),
+        Some(Commands::Push { .. }) => "push".to_string(),
         Some(Commands::Fetch { .. }) => "fetch".to_string(),
         Some(Commands::Status { .. }) => "status".to_string(),
+        Some(Commands::Sync { .. }) => "sync".to_string(),
+        Some(Commands::Clone { .. }) => "clone".to_string(),
         Some(Commands::Meta { .. }) => "meta".to_string(),
+        Some(Commands::Roots { .. }) => "roots".to_string(),
         Some(Commands::External(args)) => args
             .first()
             .cloned()
@@ -104,6 +315,38 @@ fn command_label(command: &Option<Commands>) -> String {
     }
 }
 
+/// Print an end-of-run summary, keeping stdout parseable for machine-readable formats
+fn print_summary(format: OutputFormat, summary: &str) {
+    if format == OutputFormat::Text {
+        println!();
+        println!("{}", summary);
+    } else {
+        eprintln!("{}", summary);
+    }
+}
+
+/// List repos dropped by state selection, for `--dry-run`
+fn print_excluded(excluded: &[Excluded], ctx: &ExecutionContext) {
+    if excluded.is_empty() {
+        return;
+    }
+    println!(
+        "Excluded {} by selection:",
+        pluralize(excluded.len(), "repository", "repositories")
+    );
+    for exclusion in excluded {
+        println!(
+            "  {}: {}",
+            ctx.display_name(&exclusion.repo),
+            exclusion.reason
+        );
+    }
+}
+
+fn pluralize(count: usize, singular: &str, plural: &str) -> String {
+    format!("{} {}", count, if count == 1 { singular } else { plural })
+}
+
 /// Exec git with all original args, replacing the git-all process.
 /// This is used when git-all is invoked from inside a git repository.
 #[cfg(unix)]
@@ -125,64 +368,259 @@ fn passthrough_to_git() -> ! {
     std::process::exit(status.code().unwrap_or(1));
 }
 
-fn main() -> Result<()> {
+/// Whether to run across repos even from inside one: `--all`,
+/// `GIT_ALL_MODE=multi`, or options that say where the repos are
+fn forces_multi_repo(args: &[String]) -> Result<bool> {
+    match std::env::var(MODE_ENV) {
+        Ok(mode) if mode.eq_ignore_ascii_case("multi") => return Ok(true),
+        Ok(mode) if mode.is_empty() || mode.eq_ignore_ascii_case("auto") => {}
+        Ok(mode) => bail!("{MODE_ENV} must be \"auto\" or \"multi\", got {mode:?}"),
+        Err(_) => {}
+    }
+    // Only git-all's own options count: `git-all fetch --all` still passes through
+    let argv = std::iter::once("git-all").chain(args.iter().map(String::as_str));
+    let Ok(cli) = Cli::try_parse_from(argv) else {
+        return Ok(false);
+    };
+    Ok(cli.all || cli.root.is_some() || cli.repos_from.is_some())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            ExitCode::from(EXIT_GIT_ALL_FAILURE)
+        }
+    }
+}
+
+/// Print a clap error and exit. Help and version requests exit 0; rejected
+/// command lines are git-all failures (SPEC 8.3) rather than clap's usual 2.
+fn exit_for_clap_error(err: clap::Error) -> ! {
+    let _ = err.print();
+    let code = if err.use_stderr() {
+        EXIT_GIT_ALL_FAILURE
+    } else {
+        0
+    };
+    std::process::exit(code.into())
+}
+
+fn run() -> Result<ExitCode> {
     let args: Vec<String> = std::env::args().skip(1).collect();
     let is_meta = args.first().map(|s| s == "meta").unwrap_or(false);
+    // git-all-roots ignores the cwd, so it never passes through
+    let roots_mode = roots::is_roots_invocation();
 
-    if !is_meta && is_inside_git_repo() {
+    if !is_meta && !roots_mode && !forces_multi_repo(&args)? && is_inside_git_repo() {
         passthrough_to_git();
     }
 
     let mut trace = TraceSink::from_env()?;
-    let cli = Cli::parse();
+    let matches = Cli::command()
+        .try_get_matches()
+        .unwrap_or_else(|err| exit_for_clap_error(err));
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| exit_for_clap_error(err));
+    let cwd = std::env::current_dir()?;
+    // Where discovery starts and what repo names are relative to
+    let root = match &cli.root {
+        Some(_) if roots_mode => bail!("--root can't be used with git-all-roots"),
+        Some(dir) if !cwd.join(dir).is_dir() => {
+            bail!("--root {}: not a directory", dir.display())
+        }
+        Some(dir) => cwd.join(dir),
+        None => cwd.clone(),
+    };
 
     if let Some(Commands::Meta { args }) = &cli.command {
-        meta::run(args);
-        return Ok(());
+        meta::run(args, &cli, &matches, &root)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    if let Some(Commands::Roots { action }) = &cli.command {
+        roots::run(action.as_ref().unwrap_or(&RootsAction::List), &cwd)?;
+        return Ok(ExitCode::SUCCESS);
     }
 
-    let cwd = std::env::current_dir()?;
+    let files = ConfigFiles::load(&root)?;
+    let settings = Settings::resolve(&cli, &matches, &files)?;
+    let groups = files.groups();
+    let group_filter = GroupFilter::new(&cli.group);
+    let discovery = DiscoveryOptions {
+        include_bare: cli.include_bare,
+        worktrees: cli.worktrees,
+        recurse_submodules: cli.recurse_submodules,
+        prune: settings.prune.value.clone(),
+        cache_dir: settings
+            .discovery_cache
+            .value
+            .then(user_cache_dir)
+            .flatten(),
+        follow_symlinks: !cli.no_follow_symlinks,
+        one_file_system: cli.one_file_system,
+    };
+    let workers = settings.workers.value;
+    let format = settings.format.value;
+
     let scan_started_at = Instant::now();
-    let repos = find_git_repos_in(&cwd, cli.scan_depth)?;
+    let filter = RepoFilter::new(&settings.include.value, &settings.exclude.value)?;
+    let roots = if roots_mode {
+        let registry = Registry::load_default()?;
+        if registry.roots().is_empty() {
+            println!("No roots registered. Add one with: git-all-roots roots add <path>");
+            return Ok(ExitCode::from(EXIT_GIT_ALL_FAILURE));
+        }
+        registry.roots().to_vec()
+    } else {
+        Vec::new()
+    };
+    // clone lists its repos in the manifest; they needn't exist yet
+    let clone_entries = match &cli.command {
+        Some(Commands::Clone { manifest, .. }) => {
+            if cli.repos_from.is_some() || !cli.group.is_empty() {
+                bail!(
+                    "clone reads its repositories from the manifest; drop --repos-from and --group"
+                );
+            }
+            Some(clone_manifest::read_manifest(manifest, &cwd, &root)?)
+        }
+        _ => None,
+    };
+    let mut repos = if let Some(entries) = &clone_entries {
+        entries.iter().map(|entry| entry.path.clone()).collect()
+    } else if let Some(source) = &cli.repos_from {
+        manifest::read_repos(source, &cwd)?
+    } else if group_filter.replaces_discovery() {
+        group_filter.included(&groups)?
+    } else if roots_mode {
+        roots::find_git_repos_in_roots(&roots, settings.scan_depth.value, &discovery)?
+    } else {
+        discover_repos(&root, settings.scan_depth.value, &discovery)?
+    };
+    group_filter.remove_excluded(&groups, &mut repos)?;
+    repos.retain(|repo| {
+        let root = owning_root(repo, &roots).unwrap_or(&root);
+        filter.allows(&repo_display_name(repo, root))
+    });
     trace.emit_scan(
         &command_label(&cli.command),
-        &cwd,
+        &root,
         repos.len(),
-        cli.workers,
+        workers,
         scan_started_at.elapsed().as_millis(),
     )?;
     if repos.is_empty() {
-        println!("No git repositories found in current directory");
-        return Ok(());
+        let place = if let Some(Commands::Clone { manifest, .. }) = &cli.command {
+            manifest.clone()
+        } else if let Some(source) = &cli.repos_from {
+            if source == manifest::STDIN {
+                "the list from stdin".to_string()
+            } else {
+                source.clone()
+            }
+        } else if group_filter.replaces_discovery() {
+            "the selected groups".to_string()
+        } else if roots_mode {
+            "registered roots".to_string()
+        } else if let Some(dir) = &cli.root {
+            dir.display().to_string()
+        } else {
+            "current directory".to_string()
+        };
+        if filter.is_active() {
+            println!("No git repositories matched --include/--exclude in {place}");
+        } else {
+            println!("No git repositories found in {place}");
+        }
+        return Ok(ExitCode::from(EXIT_GIT_ALL_FAILURE));
     }
 
-    let url_scheme = if cli.ssh {
-        Some(UrlScheme::Ssh)
-    } else if cli.https {
-        Some(UrlScheme::Https)
-    } else {
-        None
+    let selection = Selection {
+        only_dirty: cli.only_dirty,
+        only_clean: cli.only_clean,
+        only_behind: cli.only_behind,
+        only_ahead: cli.only_ahead,
+        on_branch: cli.on_branch.as_ref().map(Glob::compile_matcher),
+        not_on_branch: cli.not_on_branch.as_ref().map(Glob::compile_matcher),
+        has_remote: cli.has_remote.clone(),
+        remote_host: cli.remote_host.clone(),
     };
+    if clone_entries.is_some() && selection.is_active() {
+        bail!("clone can't select repositories by state; use --include/--exclude");
+    }
+    let (repos, excluded) = selection.apply(repos, workers);
 
-    let mut ctx = ExecutionContext::new(cli.dry_run, url_scheme, cli.ssh_multiplexing, cli.workers, cwd, trace);
+    let invocation = GitInvocationOptions {
+        url_scheme: settings.url_scheme.value,
+        url_hosts: settings.url_hosts.value.clone(),
+        ssh_multiplexing: settings.ssh_multiplexing.value,
+        ssh_pool: (settings.ssh_masters.value > 0).then_some(PoolSize {
+            masters: settings.ssh_masters.value,
+            sessions: settings.ssh_master_sessions.value,
+        }),
+        timeout: settings.timeout.value,
+    };
+    let retry = RetryPolicy::new(settings.retries.value, &cli.retry_on);
+    let limits = WorkerLimits {
+        workers,
+        per_host: settings.host_workers.value.into_iter().collect(),
+    };
+    let mut ctx = ExecutionContext::new(
+        cli.dry_run,
+        invocation,
+        limits,
+        root.clone(),
+        format,
+        retry,
+        trace,
+    )
+    .with_roots(roots);
 
     if cli.dry_run {
         println!(
             "[git-all v{}] Running in **dry-run mode**, no git commands will be executed. Planned git commands below.",
             env!("CARGO_PKG_VERSION")
         );
+        print_excluded(&excluded, &ctx);
+    }
+
+    if repos.is_empty() {
+        println!("No git repositories matched the selection");
+        return Ok(ExitCode::from(EXIT_GIT_ALL_FAILURE));
+    }
+
+    if !cli.dry_run {
+        cancel::install_handler()?;
     }
 
-    match cli.command {
-        Some(Commands::Pull { args }) => pull::run(&mut ctx, &repos, &args),
-        Some(Commands::Fetch { args }) => fetch::run(&mut ctx, &repos, &args),
-        Some(Commands::Status { args }) => status::run(&mut ctx, &repos, &args),
-        Some(Commands::External(args)) => passthrough::run(&mut ctx, &repos, &args),
-        Some(Commands::Meta { .. }) => unreachable!(), // handled above
+    let outcomes = match cli.command {
+        Some(Commands::Pull { args }) => pull::run(&mut ctx, &repos, &args)?,
+        Some(Commands::Push { args }) => push::run(&mut ctx, &repos, &args)?,
+        Some(Commands::Fetch { args }) => fetch::run(&mut ctx, &repos, &args)?,
+        Some(Commands::Status { args }) => status::run(&mut ctx, &repos, &args)?,
+        Some(Commands::Sync { args }) => sync::run(&mut ctx, &repos, &args)?,
+        Some(Commands::Clone { args, .. }) => {
+            let entries = clone_entries.as_deref().unwrap_or_default();
+            clone::run(&mut ctx, &repos, entries, &root, &args)?
+        }
+        Some(Commands::External(args)) => passthrough::run(&mut ctx, &repos, &args)?,
+        Some(Commands::Meta { .. } | Commands::Roots { .. }) => unreachable!(), // handled above
         None => {
             // No command given - show help
             println!("No command specified. Use --help for usage information.");
-            Ok(())
+            return Ok(ExitCode::SUCCESS);
         }
+    };
+
+    if cancel::requested() {
+        print_summary(format, &interruption_summary(&outcomes));
+        return Ok(ExitCode::from(EXIT_INTERRUPTED));
     }
+
+    if let Some(summary) = failure_summary(&outcomes) {
+        print_summary(format, &summary);
+        return Ok(ExitCode::from(EXIT_REPO_FAILURE));
+    }
+
+    Ok(ExitCode::SUCCESS)
 }
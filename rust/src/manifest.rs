@@ -0,0 +1,301 @@
+//! Reading the repo list from a file or stdin (`--repos-from`) instead of
+//! scanning directories.
+//!
+//! Supported formats, detected from the file name or contents:
+//! - plain text: one path per line, `#` comments
+//! - a `repo` tool manifest (`<manifest><project path=... name=...>`)
+//! - a myrepos `.mrconfig` (one `[path]` section per repo)
+//! - a `.gitmodules` file (`path = ...` per submodule)
+
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::config::expand_home;
+use crate::repo::parse_gitmodules;
+
+/// `--repos-from` value meaning "read the list from stdin"
+pub const STDIN: &str = "-";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Plain,
+    RepoManifest,
+    MrConfig,
+    GitModules,
+}
+
+impl Format {
+    fn detect(path: Option<&Path>, contents: &str) -> Self {
+        let name = path
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let first_line = contents
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with(';'))
+            .unwrap_or("");
+
+        if name.ends_with(".xml") || first_line.starts_with('<') {
+            Format::RepoManifest
+        } else if name == ".gitmodules" || first_line.starts_with("[submodule ") {
+            Format::GitModules
+        } else if name == ".mrconfig" || first_line.starts_with('[') {
+            Format::MrConfig
+        } else {
+            Format::Plain
+        }
+    }
+}
+
+/// Read repo paths from `source` (a file, or `-` for stdin), keeping only
+/// existing git repositories, sorted like directory discovery. Entries that
+/// can't be used are reported on stderr.
+pub fn read_repos(source: &str, cwd: &Path) -> Result<Vec<PathBuf>> {
+    let (path, contents) = read_source(source, cwd)?;
+
+    // Relative entries resolve against the list's directory; stdin uses the cwd
+    let base = path
+        .as_deref()
+        .and_then(Path::parent)
+        .unwrap_or(cwd)
+        .to_path_buf();
+    let (entries, base) = match Format::detect(path.as_deref(), &contents) {
+        Format::Plain => (parse_plain(&contents), base),
+        Format::RepoManifest => (
+            parse_repo_manifest(&contents, path.as_deref())?,
+            repo_checkout_root(&base),
+        ),
+        Format::MrConfig => (parse_mrconfig(&contents), base),
+        Format::GitModules => (parse_gitmodules(&contents), base),
+    };
+
+    let mut repos = Vec::new();
+    for entry in entries {
+        let repo = expand_home(&entry).unwrap_or_else(|| base.join(&entry));
+        if !repo.join(".git").exists() {
+            let problem = if repo.exists() {
+                "not a git repository"
+            } else {
+                "does not exist"
+            };
+            eprintln!("git-all: skipping {} ({problem})", repo.display());
+            continue;
+        }
+        repos.push(repo.canonicalize().unwrap_or(repo));
+    }
+    repos.sort();
+    repos.dedup();
+    Ok(repos)
+}
+
+/// Contents of `source` (a file relative to `cwd`, or `-` for stdin), with
+/// the file's path when there is one
+pub fn read_source(source: &str, cwd: &Path) -> Result<(Option<PathBuf>, String)> {
+    if source == STDIN {
+        let mut contents = String::new();
+        std::io::stdin()
+            .read_to_string(&mut contents)
+            .context("failed to read repository list from stdin")?;
+        Ok((None, contents))
+    } else {
+        let path = cwd.join(source);
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        Ok((Some(path), contents))
+    }
+}
+
+fn parse_plain(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.trim_end_matches('/').to_string())
+        .collect()
+}
+
+/// Projects from a `repo` manifest, following `<include>` and honoring
+/// `<remove-project>`. A project's checkout path defaults to its name.
+fn parse_repo_manifest(contents: &str, path: Option<&Path>) -> Result<Vec<String>> {
+    let mut projects = Vec::new();
+    collect_manifest_projects(contents, path, &mut projects, 0)?;
+    Ok(projects.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Deep enough for real manifests, shallow enough to stop include cycles
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+fn collect_manifest_projects(
+    contents: &str,
+    path: Option<&Path>,
+    projects: &mut Vec<(String, String)>,
+    depth: usize,
+) -> Result<()> {
+    let describe = || {
+        path.map(|path| path.display().to_string())
+            .unwrap_or_else(|| "stdin".to_string())
+    };
+    if depth > MAX_INCLUDE_DEPTH {
+        bail!("manifest includes nest too deeply at {}", describe());
+    }
+    let document = roxmltree::Document::parse(contents)
+        .with_context(|| format!("invalid manifest XML in {}", describe()))?;
+    let root = document.root_element();
+    if root.tag_name().name() != "manifest" {
+        bail!("{} is not a repo manifest: expected <manifest>", describe());
+    }
+
+    for node in root.children().filter(roxmltree::Node::is_element) {
+        match node.tag_name().name() {
+            "project" => {
+                let Some(name) = node.attribute("name") else {
+                    continue;
+                };
+                let checkout = node.attribute("path").unwrap_or(name);
+                projects.push((name.to_string(), checkout.to_string()));
+            }
+            "remove-project" => {
+                if let Some(name) = node.attribute("name") {
+                    projects.retain(|(project, _)| project != name);
+                }
+            }
+            "include" => {
+                let (Some(name), Some(dir)) = (node.attribute("name"), path.and_then(Path::parent))
+                else {
+                    continue;
+                };
+                let included = dir.join(name);
+                let contents = fs::read_to_string(&included)
+                    .with_context(|| format!("failed to read {}", included.display()))?;
+                collect_manifest_projects(&contents, Some(&included), projects, depth + 1)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Manifests usually live in `<top>/.repo/manifests/`; projects are relative to `<top>`
+fn repo_checkout_root(manifest_dir: &Path) -> PathBuf {
+    manifest_dir
+        .ancestors()
+        .find(|dir| dir.file_name().is_some_and(|name| name == ".repo"))
+        .and_then(Path::parent)
+        .unwrap_or(manifest_dir)
+        .to_path_buf()
+}
+
+/// Section names from a myrepos `.mrconfig`, skipping `[DEFAULT]`
+fn parse_mrconfig(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter_map(|line| line.strip_prefix('[')?.strip_suffix(']'))
+        .map(str::trim)
+        .filter(|section| !section.is_empty() && *section != "DEFAULT")
+        .map(|section| section.trim_end_matches('/').to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(Format::detect(None, "api\nweb\n"), Format::Plain);
+        assert_eq!(
+            Format::detect(None, "<?xml version=\"1.0\"?>\n<manifest/>"),
+            Format::RepoManifest
+        );
+        assert_eq!(
+            Format::detect(Some(Path::new("/m/default.xml")), ""),
+            Format::RepoManifest
+        );
+        assert_eq!(
+            Format::detect(None, "# repos\n[src/api]\ncheckout = git clone x api\n"),
+            Format::MrConfig
+        );
+        assert_eq!(
+            Format::detect(Some(Path::new("/w/.gitmodules")), ""),
+            Format::GitModules
+        );
+        assert_eq!(
+            Format::detect(None, "[submodule \"lib\"]\n\tpath = lib\n"),
+            Format::GitModules
+        );
+    }
+
+    #[test]
+    fn test_parse_plain_skips_comments_and_blanks() {
+        assert_eq!(
+            parse_plain("# services\napi/\n\n  web  \n"),
+            vec!["api", "web"]
+        );
+    }
+
+    #[test]
+    fn test_parse_repo_manifest() {
+        let manifest = r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest>
+  <remote name="aosp" fetch=".."/>
+  <default revision="main" remote="aosp"/>
+  <project path="build/make" name="platform/build"/>
+  <project name="platform/art"/>
+  <project path="old" name="platform/old"/>
+  <remove-project name="platform/old"/>
+</manifest>"#;
+        assert_eq!(
+            parse_repo_manifest(manifest, None).unwrap(),
+            vec!["build/make", "platform/art"]
+        );
+        assert!(parse_repo_manifest("<projects/>", None).is_err());
+    }
+
+    #[test]
+    fn test_repo_manifest_includes() {
+        let temp = tempfile::tempdir().unwrap();
+        let manifests = temp.path().join(".repo/manifests");
+        fs::create_dir_all(&manifests).unwrap();
+        fs::write(
+            manifests.join("extra.xml"),
+            r#"<manifest><project name="tools/lint"/></manifest>"#,
+        )
+        .unwrap();
+        let main = manifests.join("default.xml");
+        let contents = r#"<manifest><project name="app"/><include name="extra.xml"/></manifest>"#;
+
+        assert_eq!(
+            parse_repo_manifest(contents, Some(&main)).unwrap(),
+            vec!["app", "tools/lint"]
+        );
+        assert_eq!(repo_checkout_root(&manifests), temp.path());
+    }
+
+    #[test]
+    fn test_parse_mrconfig() {
+        let mrconfig = "[DEFAULT]\njobs = 4\n\n[src/api]\ncheckout = git clone git@github.com:org/api.git api\n\n[src/web/]\n";
+        assert_eq!(parse_mrconfig(mrconfig), vec!["src/api", "src/web"]);
+    }
+
+    #[test]
+    fn test_read_repos_resolves_against_the_list_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        fs::create_dir_all(root.join("meta")).unwrap();
+        fs::create_dir_all(root.join("web/.git")).unwrap();
+        fs::create_dir_all(root.join("api/.git")).unwrap();
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(
+            root.join("meta/repos.txt"),
+            "../web\n../api\n../docs\n../gone\n../api\n",
+        )
+        .unwrap();
+
+        let repos = read_repos("meta/repos.txt", &root).unwrap();
+        assert_eq!(repos, vec![root.join("api"), root.join("web")]);
+    }
+}
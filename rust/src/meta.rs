@@ -1,17 +1,28 @@
-use clap::CommandFactory;
+use anyhow::Result;
+use clap::{ArgMatches, CommandFactory};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use crate::Cli;
+use crate::config::{ConfigFiles, Settings, WORKSPACE_CONFIG_FILE};
+use crate::groups::Resolved;
+use crate::repo::repo_display_name;
 
-pub fn run(args: &[String]) {
+/// Widest `key = value` that `meta config` aligns source comments after
+const MAX_ALIGNED_WIDTH: usize = 40;
+
+pub fn run(args: &[String], cli: &Cli, matches: &ArgMatches, cwd: &Path) -> Result<()> {
     match args.first().map(|s| s.as_str()) {
         None | Some("help") => print_help(),
+        Some("config") => print_config(cli, matches, cwd)?,
+        Some("groups") => print_groups(cwd)?,
         Some(unknown) => {
             eprintln!("Unknown meta subcommand: {}", unknown);
-            eprintln!("Available: help");
+            eprintln!("Available: help, config, groups");
             std::process::exit(1);
         }
     }
+    Ok(())
 }
 
 fn print_help() {
@@ -28,6 +39,76 @@ fn print_help() {
     println!();
 }
 
+/// Print the effective settings and which layer each one came from
+fn print_config(cli: &Cli, matches: &ArgMatches, cwd: &Path) -> Result<()> {
+    let files = ConfigFiles::load(cwd)?;
+    let settings = Settings::resolve(cli, matches, &files)?;
+
+    println!(
+        "user config:      {}",
+        describe_path(&files.user_path, "none ($HOME is not set)")
+    );
+    println!(
+        "workspace config: {}",
+        describe_path(
+            &files.workspace_path,
+            &format!("none (no {WORKSPACE_CONFIG_FILE} here or above)")
+        )
+    );
+    println!();
+
+    let lines = settings.describe();
+    // Long values (like the prune list) shouldn't push every comment far right
+    let width = lines
+        .iter()
+        .map(|(key, value, _)| key.len() + value.len() + 3)
+        .filter(|&len| len <= MAX_ALIGNED_WIDTH)
+        .max()
+        .unwrap_or(0);
+    for (key, value, source) in lines {
+        let assignment = format!("{key} = {value}");
+        println!("{assignment:<width$}  # {source}");
+    }
+    Ok(())
+}
+
+/// List each group's repos, flagging members that resolve to nothing
+fn print_groups(cwd: &Path) -> Result<()> {
+    let groups = ConfigFiles::load(cwd)?.groups();
+    if groups.is_empty() {
+        println!(
+            "No groups defined. Add a [groups] table to {WORKSPACE_CONFIG_FILE} or the user config."
+        );
+        return Ok(());
+    }
+
+    for (idx, group) in groups.iter().enumerate() {
+        if idx > 0 {
+            println!();
+        }
+        println!("{}  # {}", group.name, group.source);
+        for (entry, resolved) in group.resolve() {
+            match resolved {
+                Resolved::Repos(repos) => {
+                    for repo in repos {
+                        println!("  {}", repo_display_name(&repo, cwd));
+                    }
+                }
+                problem => println!("  {entry}  (!) {}", problem.describe()),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn describe_path(path: &Option<PathBuf>, missing: &str) -> String {
+    match path {
+        Some(path) if path.is_file() => path.display().to_string(),
+        Some(path) => format!("{} (not found)", path.display()),
+        None => missing.to_string(),
+    }
+}
+
 fn get_git_version() -> String {
     Command::new("git")
         .arg("--version")
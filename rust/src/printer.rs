@@ -0,0 +1,774 @@
+use crossterm::{cursor, queue, terminal};
+use serde_json::{Map, Value, json};
+use std::io::{self, IsTerminal, Write};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::process::Output;
+use std::time::{Duration, Instant};
+
+use crate::roots::root_label;
+
+const MIN_REPO_NAME_WIDTH: usize = 4;
+const MAX_REPO_NAME_WIDTH_CAP: usize = 48;
+const MIN_BRANCH_WIDTH: usize = 4;
+const MAX_BRANCH_WIDTH_CAP: usize = 32;
+const ELLIPSIS: &str = "...";
+
+/// How results are written to stdout
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable table (live on a TTY, plain lines otherwise)
+    #[default]
+    Text,
+    /// One JSON array of per-repo records, printed when the run completes
+    Json,
+    /// One JSON record per line, streamed as each repo completes
+    Ndjson,
+}
+
+/// Lifecycle of a single repository row
+pub enum RowState {
+    /// Waiting for a worker
+    Pending,
+    /// git is running
+    Running,
+    /// git exited (or failed to spawn)
+    Done(Completion),
+}
+
+/// A finished repository: the formatter's one-line message and structured
+/// fields, plus the raw result
+pub struct Completion {
+    pub message: String,
+    pub fields: Map<String, Value>,
+    pub duration: Duration,
+    /// How many times git ran; more than 1 when transient failures were retried
+    pub attempts: u32,
+    /// Branch checked out once the command finished, when it can be read
+    pub branch: Option<String>,
+    pub result: Result<Output, io::Error>,
+}
+
+/// Rendering-neutral state for one repository, in discovery order
+pub struct Row {
+    pub name: String,
+    pub branch: String,
+    pub path: PathBuf,
+    pub argv: Vec<String>,
+    /// Registered root the repo was found under, in `git-all-roots` mode
+    pub root: Option<PathBuf>,
+    pub state: RowState,
+}
+
+impl Row {
+    pub fn new(name: String, branch: String, path: PathBuf, argv: Vec<String>) -> Self {
+        Self {
+            name,
+            branch,
+            path,
+            argv,
+            root: None,
+            state: RowState::Pending,
+        }
+    }
+
+    pub fn with_root(mut self, root: Option<PathBuf>) -> Self {
+        self.root = root;
+        self
+    }
+
+    /// Mark the row finished, taking the branch the command left checked
+    /// out (a fresh clone has none until it runs)
+    fn finish(&mut self, completion: Completion) {
+        if let Some(branch) = &completion.branch {
+            self.branch = branch.clone();
+        }
+        self.state = RowState::Done(completion);
+    }
+
+    fn is_done(&self) -> bool {
+        matches!(self.state, RowState::Done(_))
+    }
+
+    /// Text for the message column in the live table
+    fn status_text(&self) -> &str {
+        match &self.state {
+            RowState::Pending => "pending",
+            RowState::Running => "running",
+            RowState::Done(completion) => &completion.message,
+        }
+    }
+}
+
+/// Widths for the repo and branch columns of the `repo | branch | message` layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColumnWidths {
+    repo: usize,
+    branch: usize,
+}
+
+impl ColumnWidths {
+    /// Size each column from the actual values in this run, clamped to [min, cap]
+    pub fn compute(rows: &[Row]) -> Self {
+        Self {
+            repo: compute_column_width(
+                rows.iter().map(|row| row.name.as_str()),
+                MIN_REPO_NAME_WIDTH,
+                MAX_REPO_NAME_WIDTH_CAP,
+            ),
+            branch: compute_column_width(
+                rows.iter().map(|row| row.branch.as_str()),
+                MIN_BRANCH_WIDTH,
+                MAX_BRANCH_WIDTH_CAP,
+            ),
+        }
+    }
+}
+
+fn compute_column_width<'a>(
+    values: impl Iterator<Item = &'a str>,
+    min: usize,
+    cap: usize,
+) -> usize {
+    let max_len = values.map(|value| value.chars().count()).max().unwrap_or(0);
+    max_len.min(cap).max(min)
+}
+
+/// Fit a value into a column: truncate with a trailing ellipsis, pad short ones
+fn format_column(value: &str, width: usize) -> String {
+    let display_value = if value.chars().count() > width {
+        if width <= ELLIPSIS.len() {
+            value.chars().take(width).collect()
+        } else {
+            let kept: String = value.chars().take(width - ELLIPSIS.len()).collect();
+            format!("{}{}", kept, ELLIPSIS)
+        }
+    } else {
+        value.to_string()
+    };
+    format!("{:<width$}", display_value, width = width)
+}
+
+/// Render one `<repo> | <branch> | <message>` row (SPEC 7.1.1)
+fn format_row(name: &str, branch: &str, message: &str, widths: ColumnWidths) -> String {
+    format!(
+        "{} | {} | {}",
+        format_column(name, widths.repo),
+        format_column(branch, widths.branch),
+        message
+    )
+}
+
+/// Heading printed above the rows of each root, e.g. `~/src`. Returns `None`
+/// when `row` continues the previous row's root (or there are no roots).
+fn group_heading(row: &Row, previous: Option<&Row>) -> Option<String> {
+    let root = row.root.as_deref()?;
+    if previous.is_some_and(|previous| previous.root.as_deref() == Some(root)) {
+        return None;
+    }
+    Some(root_label(root))
+}
+
+/// Rows under a root heading are indented beneath it
+fn indent_for(row: &Row) -> &'static str {
+    if row.root.is_some() { "  " } else { "" }
+}
+
+/// Label line printed before a repository's verbatim output
+fn format_label(name: &str) -> String {
+    format!("==> {} <==", name)
+}
+
+/// Presentation of a run. The runner owns execution and reports row changes;
+/// printers decide when and how rows reach the terminal.
+pub trait Printer {
+    /// Called once before any command is spawned
+    fn start(&mut self) -> io::Result<()>;
+
+    /// The repo at `idx` acquired a worker and its command was spawned
+    fn row_started(&mut self, idx: usize) -> io::Result<()>;
+
+    /// The repo at `idx` finished. Returns the indices that became visible
+    /// as a result, in the order they were printed.
+    fn row_finished(&mut self, idx: usize, completion: Completion) -> io::Result<Vec<usize>>;
+
+    /// Called once after every repo has finished. Returns any indices that
+    /// were held back until the end of the run.
+    fn finish(&mut self) -> io::Result<Vec<usize>>;
+}
+
+/// Pick the printer for this run. Text output uses the live table for
+/// interactive terminals and the plain ordered printer for pipes, redirects
+/// and verbatim commands.
+pub fn select(
+    rows: Vec<Row>,
+    verbatim: bool,
+    format: OutputFormat,
+) -> io::Result<Box<dyn Printer>> {
+    match format {
+        OutputFormat::Json => Ok(Box::new(JsonPrinter::new(rows, false))),
+        OutputFormat::Ndjson => Ok(Box::new(JsonPrinter::new(rows, true))),
+        OutputFormat::Text if !verbatim && io::stdout().is_terminal() => {
+            Ok(Box::new(TtyTablePrinter::new(rows)?))
+        }
+        OutputFormat::Text => Ok(Box::new(PlainPrinter::new(rows, verbatim))),
+    }
+}
+
+/// Append-only printer that emits rows in discovery order as soon as every
+/// earlier row has finished (head-of-line blocking).
+pub struct PlainPrinter {
+    rows: Vec<Row>,
+    widths: ColumnWidths,
+    verbatim: bool,
+    next_to_print: usize,
+}
+
+impl PlainPrinter {
+    pub fn new(rows: Vec<Row>, verbatim: bool) -> Self {
+        let widths = ColumnWidths::compute(&rows);
+        Self {
+            rows,
+            widths,
+            verbatim,
+            next_to_print: 0,
+        }
+    }
+
+    fn print_row(&self, idx: usize) -> io::Result<()> {
+        let row = &self.rows[idx];
+        let RowState::Done(completion) = &row.state else {
+            return Ok(());
+        };
+
+        let previous = idx.checked_sub(1).map(|previous| &self.rows[previous]);
+        if let Some(heading) = group_heading(row, previous) {
+            writeln!(io::stdout().lock(), "{}", heading)?;
+        }
+
+        if self.verbatim {
+            return print_verbatim(&row.name, completion);
+        }
+
+        let line = format_row(&row.name, &row.branch, &completion.message, self.widths);
+        writeln!(io::stdout().lock(), "{}{}", indent_for(row), line)
+    }
+}
+
+impl Printer for PlainPrinter {
+    fn start(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn row_started(&mut self, idx: usize) -> io::Result<()> {
+        self.rows[idx].state = RowState::Running;
+        Ok(())
+    }
+
+    fn row_finished(&mut self, idx: usize, completion: Completion) -> io::Result<Vec<usize>> {
+        self.rows[idx].finish(completion);
+
+        let mut printed = Vec::new();
+        while self.next_to_print < self.rows.len() && self.rows[self.next_to_print].is_done() {
+            self.print_row(self.next_to_print)?;
+            printed.push(self.next_to_print);
+            self.next_to_print += 1;
+        }
+        Ok(printed)
+    }
+
+    fn finish(&mut self) -> io::Result<Vec<usize>> {
+        io::stdout().flush()?;
+        Ok(Vec::new())
+    }
+}
+
+/// Print the repo label on its own line, then git's stdout and stderr as-is
+fn print_verbatim(name: &str, completion: &Completion) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{}", format_label(name))?;
+
+    match &completion.result {
+        Ok(output) => {
+            stdout.write_all(&output.stdout)?;
+            // Keep the next label on its own line when git omits the trailing newline
+            if !output.stdout.is_empty() && !output.stdout.ends_with(b"\n") {
+                writeln!(stdout)?;
+            }
+            stdout.flush()?;
+            io::stderr().lock().write_all(&output.stderr)
+        }
+        Err(_) => {
+            stdout.flush()?;
+            writeln!(io::stderr().lock(), "{}", completion.message)
+        }
+    }
+}
+
+/// Live table for interactive terminals. Every repo gets a row up front in
+/// discovery order; rows are redrawn in place as their state changes.
+pub struct TtyTablePrinter {
+    rows: Vec<Row>,
+    widths: ColumnWidths,
+    /// Rows available for repos, after reserving a line for the footer
+    capacity: usize,
+    term_width: usize,
+    started_at: Instant,
+    drawn_lines: u16,
+    /// Set between `start` and `finish`, so a run that errors out in between
+    /// still gets its cursor back on drop
+    cursor_hidden: bool,
+}
+
+impl TtyTablePrinter {
+    pub fn new(rows: Vec<Row>) -> io::Result<Self> {
+        let widths = ColumnWidths::compute(&rows);
+        // Terminal size is snapshotted once; resize isn't handled mid-run
+        let (cols, lines) = terminal::size()?;
+        Ok(Self {
+            rows,
+            widths,
+            // Some pseudo-terminals report zero lines; show every row then
+            capacity: match lines {
+                0 => usize::MAX,
+                lines => (lines as usize - 1).max(1),
+            },
+            term_width: cols as usize,
+            started_at: Instant::now(),
+            drawn_lines: 0,
+            cursor_hidden: false,
+        })
+    }
+
+    fn render(&mut self) -> io::Result<()> {
+        let first_unfinished = self
+            .rows
+            .iter()
+            .position(|row| !row.is_done())
+            .unwrap_or(self.rows.len());
+        // Leave room for one heading per root
+        let headings = self
+            .rows
+            .iter()
+            .enumerate()
+            .filter(|(idx, row)| {
+                group_heading(row, idx.checked_sub(1).map(|i| &self.rows[i])).is_some()
+            })
+            .count();
+        let capacity = self.capacity.saturating_sub(headings).max(1);
+        let window = visible_window(self.rows.len(), first_unfinished, capacity);
+        let complete = self.rows.iter().filter(|row| row.is_done()).count();
+        let running = self
+            .rows
+            .iter()
+            .filter(|row| matches!(row.state, RowState::Running))
+            .count();
+
+        let mut stdout = io::stdout().lock();
+        if self.drawn_lines > 0 {
+            queue!(stdout, cursor::MoveToPreviousLine(self.drawn_lines))?;
+        }
+        queue!(stdout, terminal::Clear(terminal::ClearType::FromCursorDown))?;
+
+        let mut drawn_lines = 0u16;
+        for idx in window.clone() {
+            let row = &self.rows[idx];
+            // The first visible row always shows its root, even mid-group
+            let previous = idx
+                .checked_sub(1)
+                .filter(|&previous| previous >= window.start);
+            if let Some(heading) = group_heading(row, previous.map(|previous| &self.rows[previous]))
+            {
+                writeln!(stdout, "{}", fit_to_width(&heading, self.term_width))?;
+                drawn_lines += 1;
+            }
+            let line = format!(
+                "{}{}",
+                indent_for(row),
+                format_row(&row.name, &row.branch, row.status_text(), self.widths)
+            );
+            writeln!(stdout, "{}", fit_to_width(&line, self.term_width))?;
+            drawn_lines += 1;
+        }
+
+        let footer = format_footer(
+            &window,
+            self.rows.len(),
+            complete,
+            running,
+            self.started_at.elapsed().as_secs_f64(),
+        );
+        writeln!(stdout, "{}", fit_to_width(&footer, self.term_width))?;
+        self.drawn_lines = drawn_lines + 1;
+
+        stdout.flush()
+    }
+}
+
+impl Printer for TtyTablePrinter {
+    fn start(&mut self) -> io::Result<()> {
+        queue!(io::stdout(), cursor::Hide)?;
+        self.cursor_hidden = true;
+        self.render()
+    }
+
+    fn row_started(&mut self, idx: usize) -> io::Result<()> {
+        self.rows[idx].state = RowState::Running;
+        self.render()
+    }
+
+    fn row_finished(&mut self, idx: usize, completion: Completion) -> io::Result<Vec<usize>> {
+        self.rows[idx].finish(completion);
+        self.render()?;
+        Ok(vec![idx])
+    }
+
+    fn finish(&mut self) -> io::Result<Vec<usize>> {
+        self.render()?;
+        let mut stdout = io::stdout();
+        queue!(stdout, cursor::Show)?;
+        stdout.flush()?;
+        self.cursor_hidden = false;
+        Ok(Vec::new())
+    }
+}
+
+impl Drop for TtyTablePrinter {
+    fn drop(&mut self) {
+        if self.cursor_hidden {
+            let mut stdout = io::stdout();
+            let _ = queue!(stdout, cursor::Show);
+            let _ = stdout.flush();
+        }
+    }
+}
+
+/// Machine-readable output. NDJSON streams one record per repo in completion
+/// order; JSON collects every record and prints an array in discovery order.
+pub struct JsonPrinter {
+    rows: Vec<Row>,
+    streaming: bool,
+}
+
+impl JsonPrinter {
+    pub fn new(rows: Vec<Row>, streaming: bool) -> Self {
+        Self { rows, streaming }
+    }
+}
+
+impl Printer for JsonPrinter {
+    fn start(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn row_started(&mut self, idx: usize) -> io::Result<()> {
+        self.rows[idx].state = RowState::Running;
+        Ok(())
+    }
+
+    fn row_finished(&mut self, idx: usize, completion: Completion) -> io::Result<Vec<usize>> {
+        self.rows[idx].finish(completion);
+        if !self.streaming {
+            return Ok(Vec::new());
+        }
+
+        let mut stdout = io::stdout().lock();
+        if let Some(record) = json_record(&self.rows[idx]) {
+            writeln!(stdout, "{}", record)?;
+        }
+        stdout.flush()?;
+        Ok(vec![idx])
+    }
+
+    fn finish(&mut self) -> io::Result<Vec<usize>> {
+        if self.streaming {
+            return Ok(Vec::new());
+        }
+
+        let records: Vec<Value> = self.rows.iter().filter_map(json_record).collect();
+        let mut stdout = io::stdout().lock();
+        serde_json::to_writer_pretty(&mut stdout, &records)?;
+        writeln!(stdout)?;
+        stdout.flush()?;
+        Ok((0..self.rows.len()).collect())
+    }
+}
+
+/// JSON record for a finished row, or `None` if it hasn't finished
+fn json_record(row: &Row) -> Option<Value> {
+    let RowState::Done(completion) = &row.state else {
+        return None;
+    };
+
+    let (exit_code, success, stdout, stderr, error) = match &completion.result {
+        Ok(output) => (
+            output.status.code(),
+            output.status.success(),
+            String::from_utf8_lossy(&output.stdout).into_owned(),
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+            None,
+        ),
+        Err(e) => (
+            None,
+            false,
+            String::new(),
+            String::new(),
+            Some(e.to_string()),
+        ),
+    };
+
+    Some(json!({
+        "repo": row.name,
+        "path": row.path.to_string_lossy(),
+        "root": row.root.as_ref().map(|root| root.to_string_lossy()),
+        "branch": row.branch,
+        "command": row.argv,
+        "exit_code": exit_code,
+        "success": success,
+        "error": error,
+        "duration_ms": completion.duration.as_millis() as u64,
+        "attempts": completion.attempts,
+        "summary": completion.message,
+        "fields": completion.fields,
+        "stdout": stdout,
+        "stderr": stderr,
+    }))
+}
+
+/// Rows to show when only `capacity` fit: follow the first unfinished repo so
+/// completed rows scroll off the top while alpha order is preserved.
+fn visible_window(total: usize, first_unfinished: usize, capacity: usize) -> Range<usize> {
+    if total <= capacity {
+        return 0..total;
+    }
+    let start = first_unfinished.min(total - capacity);
+    start..start + capacity
+}
+
+/// Footer line, e.g. `showing 24-47 of 98 | 41 complete | 8 running | 2.1s`
+fn format_footer(
+    window: &Range<usize>,
+    total: usize,
+    complete: usize,
+    running: usize,
+    elapsed_secs: f64,
+) -> String {
+    let first = if window.is_empty() {
+        0
+    } else {
+        window.start + 1
+    };
+    format!(
+        "showing {}-{} of {} | {} complete | {} running | {:.1}s",
+        first, window.end, total, complete, running, elapsed_secs
+    )
+}
+
+/// Clip a line to the terminal width so it never wraps and breaks in-place redraws
+fn fit_to_width(line: &str, width: usize) -> String {
+    if width == 0 {
+        return line.to_string();
+    }
+    line.chars().take(width).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(name: &str, branch: &str) -> Row {
+        Row::new(
+            name.to_string(),
+            branch.to_string(),
+            PathBuf::from("/workspace").join(name),
+            vec!["git".to_string(), "status".to_string()],
+        )
+    }
+
+    fn completion(message: &str, result: Result<Output, io::Error>) -> Completion {
+        Completion {
+            message: message.to_string(),
+            fields: Map::new(),
+            duration: Duration::from_millis(12),
+            attempts: 1,
+            branch: None,
+            result,
+        }
+    }
+
+    #[test]
+    fn test_format_column_short() {
+        let result = format_column("my-repo", 24);
+        assert_eq!(result, "my-repo                 ");
+        assert_eq!(result.len(), 24);
+    }
+
+    #[test]
+    fn test_format_column_exact_length() {
+        let result = format_column("exactly-twenty-four-char", 24);
+        assert_eq!(result, "exactly-twenty-four-char");
+    }
+
+    #[test]
+    fn test_format_column_truncated() {
+        let result = format_column("infra-services-docker-compose", 22);
+        assert_eq!(result, "infra-services-dock...");
+        assert_eq!(result.len(), 22);
+    }
+
+    #[test]
+    fn test_format_row_matches_spec_layout() {
+        let widths = ColumnWidths {
+            repo: 22,
+            branch: 16,
+        };
+        assert_eq!(
+            format_row("my-repo", "main", "clean", widths),
+            "my-repo                | main             | clean"
+        );
+        assert_eq!(
+            format_row("third-repo", "HEAD (detached)", "3 modified", widths),
+            "third-repo             | HEAD (detached)  | 3 modified"
+        );
+    }
+
+    #[test]
+    fn test_column_widths_caps_and_min() {
+        let rows = vec![
+            row("a", "main"),
+            row("short", "feature/an-extremely-long-branch-name-for-testing"),
+            row("this-is-a-very-long-repository-name-that-exceeds-cap", "b"),
+        ];
+        let widths = ColumnWidths::compute(&rows);
+        assert_eq!(widths.repo, MAX_REPO_NAME_WIDTH_CAP);
+        assert_eq!(widths.branch, MAX_BRANCH_WIDTH_CAP);
+
+        let tiny = ColumnWidths::compute(&[row("a", "b")]);
+        assert_eq!(tiny.repo, MIN_REPO_NAME_WIDTH);
+        assert_eq!(tiny.branch, MIN_BRANCH_WIDTH);
+    }
+
+    #[test]
+    fn test_row_status_text_by_state() {
+        let mut r = row("a", "main");
+        assert_eq!(r.status_text(), "pending");
+        r.state = RowState::Running;
+        assert_eq!(r.status_text(), "running");
+        r.state = RowState::Done(completion("clean", Err(io::Error::other("unused"))));
+        assert_eq!(r.status_text(), "clean");
+    }
+
+    #[test]
+    fn test_json_record_for_finished_row() {
+        use std::os::unix::process::ExitStatusExt;
+        use std::process::ExitStatus;
+
+        let mut r = row("api", "main");
+        assert!(json_record(&r).is_none());
+
+        let mut done = completion(
+            "1 modified",
+            Ok(Output {
+                status: ExitStatus::from_raw(0),
+                stdout: b"## main\n M a.txt\n".to_vec(),
+                stderr: Vec::new(),
+            }),
+        );
+        done.fields.insert("modified".to_string(), json!(1));
+        r.state = RowState::Done(done);
+
+        let record = json_record(&r).expect("finished row has a record");
+        assert_eq!(record["repo"], "api");
+        assert_eq!(record["path"], "/workspace/api");
+        assert_eq!(record["branch"], "main");
+        assert_eq!(record["command"], json!(["git", "status"]));
+        assert_eq!(record["exit_code"], 0);
+        assert_eq!(record["success"], true);
+        assert_eq!(record["error"], Value::Null);
+        assert_eq!(record["duration_ms"], 12);
+        assert_eq!(record["summary"], "1 modified");
+        assert_eq!(record["fields"]["modified"], 1);
+        assert_eq!(record["stdout"], "## main\n M a.txt\n");
+        assert_eq!(record["stderr"], "");
+    }
+
+    #[test]
+    fn test_finished_row_takes_branch_from_completion() {
+        let mut r = row("api", "unknown");
+        let mut done = completion("cloned", Err(io::Error::other("unused")));
+        done.branch = Some("main".to_string());
+        r.finish(done);
+        assert_eq!(r.branch, "main");
+
+        let mut r = row("web", "main");
+        r.finish(completion("interrupted", Err(io::Error::other("unused"))));
+        assert_eq!(r.branch, "main");
+    }
+
+    #[test]
+    fn test_json_record_for_spawn_error() {
+        let mut r = row("api", "main");
+        r.state = RowState::Done(completion(
+            "ERROR: no such file",
+            Err(io::Error::other("no such file")),
+        ));
+
+        let record = json_record(&r).expect("finished row has a record");
+        assert_eq!(record["exit_code"], Value::Null);
+        assert_eq!(record["success"], false);
+        assert_eq!(record["error"], "no such file");
+    }
+
+    #[test]
+    fn test_group_heading_starts_each_root() {
+        let src = row("api", "main").with_root(Some(PathBuf::from("/roots/src")));
+        let src2 = row("web", "main").with_root(Some(PathBuf::from("/roots/src")));
+        let work = row("app", "main").with_root(Some(PathBuf::from("/roots/work")));
+
+        assert_eq!(group_heading(&src, None).as_deref(), Some("/roots/src"));
+        assert_eq!(group_heading(&src2, Some(&src)), None);
+        assert_eq!(
+            group_heading(&work, Some(&src2)).as_deref(),
+            Some("/roots/work")
+        );
+        assert_eq!(group_heading(&row("cli", "main"), None), None);
+        assert_eq!(indent_for(&work), "  ");
+    }
+
+    #[test]
+    fn test_visible_window_shows_everything_when_it_fits() {
+        assert_eq!(visible_window(5, 0, 10), 0..5);
+        assert_eq!(visible_window(10, 3, 10), 0..10);
+    }
+
+    #[test]
+    fn test_visible_window_follows_first_unfinished() {
+        assert_eq!(visible_window(98, 0, 24), 0..24);
+        assert_eq!(visible_window(98, 23, 24), 23..47);
+    }
+
+    #[test]
+    fn test_visible_window_clamps_to_last_page() {
+        assert_eq!(visible_window(98, 90, 24), 74..98);
+        assert_eq!(visible_window(98, 98, 24), 74..98);
+    }
+
+    #[test]
+    fn test_format_footer() {
+        assert_eq!(
+            format_footer(&(23..47), 98, 41, 8, 2.14),
+            "showing 24-47 of 98 | 41 complete | 8 running | 2.1s"
+        );
+        assert_eq!(
+            format_footer(&(0..0), 0, 0, 0, 0.0),
+            "showing 0-0 of 0 | 0 complete | 0 running | 0.0s"
+        );
+    }
+
+    #[test]
+    fn test_fit_to_width() {
+        assert_eq!(fit_to_width("abcdef", 4), "abcd");
+        assert_eq!(fit_to_width("abc", 10), "abc");
+        assert_eq!(fit_to_width("abc", 0), "abc");
+    }
+}
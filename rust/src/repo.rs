@@ -1,8 +1,14 @@
 use anyhow::Result;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
+use crate::scan::{DEFAULT_PRUNE, ScanKey, scan};
+
+/// Branch column value for a repository whose HEAD is not on a branch.
+pub const DETACHED_HEAD: &str = "HEAD (detached)";
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ScanDepth {
     All,
@@ -51,40 +57,315 @@ pub fn is_inside_git_repo() -> bool {
         .unwrap_or(false)
 }
 
-/// Find all git repositories under the given root, honoring scan depth.
+/// How linked worktrees (`git worktree add`) are listed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum WorktreeMode {
+    /// One row per repository: the main checkout, or its first worktree found
+    Dedupe,
+    /// Every worktree gets a row, in the usual alphabetical order
+    #[default]
+    Separate,
+    /// Leave linked worktrees out
+    Skip,
+}
+
+/// Which kinds of repositories discovery reports, and how it walks
+#[derive(Debug, Clone)]
+pub struct DiscoveryOptions {
+    pub include_bare: bool,
+    pub worktrees: WorktreeMode,
+    pub recurse_submodules: bool,
+    /// Directory names never descended into
+    pub prune: Vec<String>,
+    /// Where to cache walk results; `None` walks every time
+    pub cache_dir: Option<PathBuf>,
+    pub follow_symlinks: bool,
+    /// Don't cross into other mounted file systems
+    pub one_file_system: bool,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        DiscoveryOptions {
+            include_bare: false,
+            worktrees: WorktreeMode::default(),
+            recurse_submodules: false,
+            prune: DEFAULT_PRUNE.iter().map(|name| name.to_string()).collect(),
+            cache_dir: None,
+            follow_symlinks: true,
+            one_file_system: false,
+        }
+    }
+}
+
+/// Find all git repositories under the given root, honoring scan depth and
+/// any `.git-all-ignore` files along the way.
 pub fn find_git_repos_in(root: &Path, scan_depth: ScanDepth) -> Result<Vec<PathBuf>> {
-    let mut repos = Vec::new();
-    scan_dir(root, 0, scan_depth.max_depth(), &mut repos)?;
+    discover_repos(root, scan_depth, &DiscoveryOptions::default())
+}
+
+/// `find_git_repos_in` with control over bare repos, worktrees and submodules.
+/// Submodules sort directly below their parent.
+pub fn discover_repos(
+    root: &Path,
+    scan_depth: ScanDepth,
+    options: &DiscoveryOptions,
+) -> Result<Vec<PathBuf>> {
+    let key = ScanKey {
+        root: root.to_path_buf(),
+        max_depth: scan_depth.max_depth(),
+        include_bare: options.include_bare,
+        prune: options.prune.clone(),
+        follow_symlinks: options.follow_symlinks,
+        one_file_system: options.one_file_system,
+    };
+    let mut repos = scan(&key, options.cache_dir.as_deref())?;
+    if options.recurse_submodules {
+        let submodules: Vec<PathBuf> = repos.iter().flat_map(|repo| submodules(repo)).collect();
+        repos.extend(submodules);
+    }
     repos.sort();
-    Ok(repos)
-}
-
-fn scan_dir(
-    dir: &Path,
-    depth: usize,
-    max_depth: Option<usize>,
-    repos: &mut Vec<PathBuf>,
-) -> Result<()> {
-    for entry in fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
-
-        if path.is_dir() {
-            let git_dir = path.join(".git");
-            if git_dir.exists() {
-                repos.push(path);
-                continue;
-            }
-
-            let next_depth = depth + 1;
-            let should_descend = max_depth.map_or(true, |max| next_depth < max);
-            if should_descend {
-                scan_dir(&path, next_depth, max_depth, repos)?;
-            }
+    Ok(arrange_worktrees(repos, options.worktrees))
+}
+
+/// Resolve the git directory for a repository, following `gitdir:` pointer
+/// files used by worktrees and submodules.
+pub fn resolve_git_dir(repo: &Path) -> Option<PathBuf> {
+    let dot_git = repo.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    if !dot_git.exists() && is_bare_repo(repo) {
+        return Some(repo.to_path_buf());
+    }
+
+    let contents = fs::read_to_string(&dot_git).ok()?;
+    let target = contents
+        .lines()
+        .find_map(|l| l.strip_prefix("gitdir:"))?
+        .trim();
+    Some(repo.join(target))
+}
+
+/// Resolve the directory holding shared state like `config`. Linked worktrees
+/// point at it through a `commondir` file; other repos use the git dir itself.
+fn resolve_common_dir(repo: &Path) -> Option<PathBuf> {
+    let git_dir = resolve_git_dir(repo)?;
+    match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(common) => Some(git_dir.join(common.trim())),
+        Err(_) => Some(git_dir),
+    }
+}
+
+/// A bare repository (e.g. a `foo.git` mirror) holds git's files directly
+pub fn is_bare_repo(path: &Path) -> bool {
+    path.join("HEAD").is_file() && path.join("objects").is_dir() && path.join("refs").is_dir()
+}
+
+/// For a linked worktree, the main repository it was created from (the bare
+/// repo itself when the main repo is bare)
+pub fn worktree_main(repo: &Path) -> Option<PathBuf> {
+    if !repo.join(".git").is_file() {
+        return None;
+    }
+    let git_dir = resolve_git_dir(repo)?;
+    // Submodules also use a `.git` file, but only worktrees have `commondir`
+    let common = fs::read_to_string(git_dir.join("commondir")).ok()?;
+    let common_dir = git_dir.join(common.trim()).canonicalize().ok()?;
+    if common_dir.file_name().is_some_and(|name| name == ".git") {
+        common_dir.parent().map(Path::to_path_buf)
+    } else {
+        Some(common_dir)
+    }
+}
+
+/// Drop linked worktrees, or all but one row per repository, as `mode` asks.
+/// `repos` must already be sorted.
+fn arrange_worktrees(repos: Vec<PathBuf>, mode: WorktreeMode) -> Vec<PathBuf> {
+    if mode == WorktreeMode::Separate {
+        return repos;
+    }
+    let mains: Vec<Option<PathBuf>> = repos.iter().map(|repo| worktree_main(repo)).collect();
+    if mains.iter().all(Option::is_none) {
+        return repos;
+    }
+    if mode == WorktreeMode::Skip {
+        return repos
+            .into_iter()
+            .zip(mains)
+            .filter_map(|(repo, main)| main.is_none().then_some(repo))
+            .collect();
+    }
+
+    // Discovered main repos by canonical path, so a main checkout sorts ahead
+    // of its worktrees and is the row dedupe keeps
+    let discovered: HashMap<PathBuf, PathBuf> = repos
+        .iter()
+        .zip(&mains)
+        .filter(|(_, main)| main.is_none())
+        .filter_map(|(repo, _)| Some((repo.canonicalize().ok()?, repo.clone())))
+        .collect();
+    let mut entries: Vec<(PathBuf, bool, PathBuf, PathBuf)> = repos
+        .into_iter()
+        .zip(mains)
+        .map(|(repo, main)| {
+            let identity = main
+                .clone()
+                .unwrap_or_else(|| repo.canonicalize().unwrap_or_else(|_| repo.clone()));
+            let anchor = main
+                .and_then(|main| discovered.get(&main).cloned())
+                .unwrap_or_else(|| repo.clone());
+            let linked = anchor != repo;
+            (anchor, linked, repo, identity)
+        })
+        .collect();
+    entries.sort();
+
+    let mut seen = HashSet::new();
+    entries
+        .into_iter()
+        .filter(|(_, _, _, identity)| seen.insert(identity.clone()))
+        .map(|(_, _, repo, _)| repo)
+        .collect()
+}
+
+/// `path = ...` values from a `.gitmodules` file
+pub fn parse_gitmodules(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            (key.trim() == "path").then(|| value.trim().to_string())
+        })
+        .collect()
+}
+
+/// Initialized submodules of `repo`, recursively
+fn submodules(repo: &Path) -> Vec<PathBuf> {
+    let Ok(contents) = fs::read_to_string(repo.join(".gitmodules")) else {
+        return Vec::new();
+    };
+    let mut found = Vec::new();
+    for path in parse_gitmodules(&contents) {
+        let submodule = repo.join(path);
+        if submodule.join(".git").exists() {
+            found.extend(submodules(&submodule));
+            found.push(submodule);
+        }
+    }
+    found
+}
+
+/// A remote declared in a repository's config
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Remote {
+    pub name: String,
+    pub url: String,
+}
+
+/// Read the remotes that have a URL straight from the repository's config
+/// file, in the order they are declared.
+pub fn remotes(repo: &Path) -> Vec<Remote> {
+    let Some(config) =
+        resolve_common_dir(repo).and_then(|dir| fs::read_to_string(dir.join("config")).ok())
+    else {
+        return Vec::new();
+    };
+
+    let mut section_remote: Option<String> = None;
+    let mut remotes = Vec::new();
+    for line in config.lines() {
+        let line = line.trim();
+        if let Some(header) = line.strip_prefix('[') {
+            section_remote = header
+                .trim_end_matches(']')
+                .strip_prefix("remote \"")
+                .map(|name| name.trim_end_matches('"').to_string());
+            continue;
+        }
+
+        let Some(remote) = &section_remote else {
+            continue;
+        };
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if !key.trim().eq_ignore_ascii_case("url") {
+            continue;
+        }
+
+        // Keep the first url when a remote lists several
+        if !remotes
+            .iter()
+            .any(|existing: &Remote| &existing.name == remote)
+        {
+            remotes.push(Remote {
+                name: remote.clone(),
+                url: value.trim().trim_matches('"').to_string(),
+            });
+        }
+    }
+
+    remotes
+}
+
+/// URL of the `origin` remote, or of the first remote when there is no origin
+pub fn remote_url(repo: &Path) -> Option<String> {
+    let remotes = remotes(repo);
+    remotes
+        .iter()
+        .find(|remote| remote.name == "origin")
+        .or_else(|| remotes.first())
+        .map(|remote| remote.url.clone())
+}
+
+/// Extract the lowercased host a remote URL connects to, e.g. `github.com` for
+/// `git@github.com:org/repo`, `https://github.com/org/repo` or
+/// `ssh://git@github.com:22/org/repo`. Local paths and `file://` URLs have no host.
+pub fn remote_host(url: &str) -> Option<String> {
+    let authority = if let Some((scheme, rest)) = url.split_once("://") {
+        if scheme.eq_ignore_ascii_case("file") {
+            return None;
+        }
+        rest.split('/').next()?
+    } else {
+        // scp-like syntax: [user@]host:path, where the colon comes before any slash
+        let (host, _) = url.split_once(':')?;
+        if host.contains('/') {
+            return None;
         }
+        host
+    };
+
+    let host_port = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = match host_port.strip_prefix('[') {
+        // Bracketed IPv6 literal, optionally followed by :port
+        Some(bracketed) => bracketed.split(']').next()?,
+        None => host_port.split(':').next()?,
+    };
+
+    if host.is_empty() {
+        None
+    } else {
+        Some(host.to_lowercase())
     }
+}
 
-    Ok(())
+/// Read the current branch for a repository straight from its `HEAD` file.
+/// Returns `DETACHED_HEAD` when HEAD points at a commit, or `None` when the
+/// git directory can't be read.
+pub fn current_branch(repo: &Path) -> Option<String> {
+    let head = fs::read_to_string(resolve_git_dir(repo)?.join("HEAD")).ok()?;
+    let branch = match head.trim().strip_prefix("ref: ") {
+        Some(reference) => reference
+            .strip_prefix("refs/heads/")
+            .unwrap_or(reference)
+            .to_string(),
+        None => DETACHED_HEAD.to_string(),
+    };
+    Some(branch)
 }
 
 /// Extract just the repository name from a path
@@ -195,6 +476,316 @@ mod tests {
         assert_eq!(depth_all, expected_depth_all);
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_find_git_repos_symlink_policy() {
+        use std::os::unix::fs::symlink;
+
+        let temp = tempfile::tempdir().expect("temp dir");
+        let outside = tempfile::tempdir().expect("temp dir");
+        let root = temp.path();
+        create_repo(root.join("real/app"), true);
+        create_repo(outside.path().join("shared/lib"), true);
+        fs::create_dir_all(root.join("links")).unwrap();
+        symlink(root.join("real/app"), root.join("links/app")).unwrap();
+        symlink(root.join("real"), root.join("links/real")).unwrap();
+        symlink(outside.path().join("shared"), root.join("links/shared")).unwrap();
+        // Loops back to the root and to its own parent
+        symlink(root, root.join("real/root")).unwrap();
+        symlink(root.join("links"), root.join("links/again")).unwrap();
+
+        // Followed by default, without looping and listing each repo once
+        assert_eq!(
+            find_git_repos_in(root, ScanDepth::All).unwrap(),
+            vec![root.join("links/shared/lib"), root.join("real/app")]
+        );
+
+        let options = DiscoveryOptions {
+            follow_symlinks: false,
+            ..DiscoveryOptions::default()
+        };
+        assert_eq!(
+            discover_repos(root, ScanDepth::All, &options).unwrap(),
+            vec![root.join("real/app")]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_git_repos_one_file_system() {
+        use std::os::unix::fs::{MetadataExt, symlink};
+
+        let temp = tempfile::tempdir().expect("temp dir");
+        let root = temp.path();
+        create_repo(root.join("local"), true);
+        // Needs a second file system; /dev/shm is a separate tmpfs on most Linux hosts
+        let Ok(other) = tempfile::tempdir_in("/dev/shm") else {
+            return;
+        };
+        let dev = |path: &Path| fs::metadata(path).unwrap().dev();
+        if dev(other.path()) == dev(root) {
+            return;
+        }
+        create_repo(other.path().join("mounted"), true);
+        symlink(other.path(), root.join("mnt")).unwrap();
+
+        let mut options = DiscoveryOptions {
+            follow_symlinks: true,
+            ..DiscoveryOptions::default()
+        };
+        assert_eq!(
+            discover_repos(root, ScanDepth::All, &options).unwrap(),
+            vec![root.join("local"), root.join("mnt/mounted")]
+        );
+        options.one_file_system = true;
+        assert_eq!(
+            discover_repos(root, ScanDepth::All, &options).unwrap(),
+            vec![root.join("local")]
+        );
+    }
+
+    #[test]
+    fn test_find_git_repos_honors_ignore_files() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let root = temp.path();
+
+        create_repo(root.join("active"), true);
+        create_repo(root.join("archive/old"), true);
+        create_repo(root.join("work/app"), true);
+        create_repo(root.join("work/scratch-1"), true);
+        fs::write(root.join(".git-all-ignore"), "archive\n").unwrap();
+        fs::write(root.join("work/.git-all-ignore"), "scratch-*\n").unwrap();
+
+        let repos = find_git_repos_in(root, ScanDepth::All).unwrap();
+        assert_eq!(repos, vec![root.join("active"), root.join("work/app")]);
+    }
+
+    #[test]
+    fn test_discover_bare_repos() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let root = temp.path();
+        create_repo(root.join("app"), true);
+        create_bare_repo(&root.join("mirrors/app.git"));
+
+        let options = DiscoveryOptions {
+            include_bare: true,
+            ..DiscoveryOptions::default()
+        };
+        assert_eq!(
+            find_git_repos_in(root, ScanDepth::All).unwrap(),
+            vec![root.join("app")]
+        );
+        assert_eq!(
+            discover_repos(root, ScanDepth::All, &options).unwrap(),
+            vec![root.join("app"), root.join("mirrors/app.git")]
+        );
+        assert_eq!(
+            resolve_git_dir(&root.join("mirrors/app.git")),
+            Some(root.join("mirrors/app.git"))
+        );
+    }
+
+    #[test]
+    fn test_discover_worktree_modes() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let root = temp.path().canonicalize().unwrap();
+        create_repo(root.join("app"), true);
+        create_repo(root.join("other"), true);
+        create_worktree(&root.join("app"), &root.join("app-feature"));
+        create_worktree(&root.join("app"), &root.join("zz-hotfix"));
+
+        let discover = |worktrees| {
+            let options = DiscoveryOptions {
+                worktrees,
+                ..DiscoveryOptions::default()
+            };
+            discover_repos(&root, ScanDepth::Depth(1), &options).unwrap()
+        };
+        assert_eq!(
+            worktree_main(&root.join("zz-hotfix")),
+            Some(root.join("app"))
+        );
+        // The default lists worktrees in plain alphabetical order
+        assert_eq!(
+            discover_repos(&root, ScanDepth::Depth(1), &DiscoveryOptions::default()).unwrap(),
+            vec![
+                root.join("app"),
+                root.join("app-feature"),
+                root.join("other"),
+                root.join("zz-hotfix"),
+            ]
+        );
+        assert_eq!(
+            discover(WorktreeMode::Separate),
+            discover_repos(&root, ScanDepth::Depth(1), &DiscoveryOptions::default()).unwrap()
+        );
+        assert_eq!(
+            discover(WorktreeMode::Dedupe),
+            vec![root.join("app"), root.join("other")]
+        );
+        assert_eq!(
+            discover(WorktreeMode::Skip),
+            vec![root.join("app"), root.join("other")]
+        );
+    }
+
+    #[test]
+    fn test_discover_submodules() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let root = temp.path();
+        create_repo(root.join("app"), true);
+        create_repo(root.join("app-tools"), true);
+        create_repo(root.join("app/vendor/lib"), false);
+        create_repo(root.join("app/vendor/lib/deps/inner"), false);
+        fs::write(
+            root.join("app/.gitmodules"),
+            "[submodule \"lib\"]\n\tpath = vendor/lib\n[submodule \"gone\"]\n\tpath = gone\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("app/vendor/lib/.gitmodules"),
+            "[submodule \"inner\"]\n\tpath = deps/inner\n",
+        )
+        .unwrap();
+
+        let options = DiscoveryOptions {
+            recurse_submodules: true,
+            ..DiscoveryOptions::default()
+        };
+        assert_eq!(
+            discover_repos(root, ScanDepth::Depth(1), &options).unwrap(),
+            vec![
+                root.join("app"),
+                root.join("app/vendor/lib"),
+                root.join("app/vendor/lib/deps/inner"),
+                root.join("app-tools"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_current_branch_reads_head() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let repo = temp.path().join("repo");
+        create_repo(repo.clone(), true);
+
+        fs::write(repo.join(".git/HEAD"), "ref: refs/heads/feature/login\n").unwrap();
+        assert_eq!(current_branch(&repo).as_deref(), Some("feature/login"));
+
+        fs::write(
+            repo.join(".git/HEAD"),
+            "0123456789abcdef0123456789abcdef01234567\n",
+        )
+        .unwrap();
+        assert_eq!(current_branch(&repo).as_deref(), Some(DETACHED_HEAD));
+    }
+
+    #[test]
+    fn test_current_branch_follows_gitdir_file() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let real_git_dir = temp.path().join("storage/repo.git");
+        fs::create_dir_all(&real_git_dir).unwrap();
+        fs::write(real_git_dir.join("HEAD"), "ref: refs/heads/main\n").unwrap();
+
+        let repo = temp.path().join("repo");
+        fs::create_dir_all(&repo).unwrap();
+        fs::write(
+            repo.join(".git"),
+            format!("gitdir: {}\n", real_git_dir.display()),
+        )
+        .unwrap();
+
+        assert_eq!(current_branch(&repo).as_deref(), Some("main"));
+    }
+
+    #[test]
+    fn test_current_branch_missing_head() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let repo = temp.path().join("repo");
+        create_repo(repo.clone(), false);
+        assert_eq!(current_branch(&repo), None);
+    }
+
+    #[test]
+    fn test_remote_host() {
+        let cases = [
+            ("git@github.com:org/repo.git", Some("github.com")),
+            ("github.com:org/repo.git", Some("github.com")),
+            ("https://github.com/org/repo", Some("github.com")),
+            ("https://u:t@GitLab.Internal/r", Some("gitlab.internal")),
+            ("ssh://git@github.com/org/repo.git", Some("github.com")),
+            ("ssh://git@gitlab.internal:22/r", Some("gitlab.internal")),
+            ("ssh://git@[::1]:2222/org/repo.git", Some("::1")),
+            ("file:///srv/mirror/repo.git", None),
+            ("/srv/mirror/repo.git", None),
+            ("../sibling", None),
+            ("./dir:with/colon", None),
+        ];
+        for (url, host) in cases {
+            assert_eq!(remote_host(url).as_deref(), host, "host for {url}");
+        }
+    }
+
+    #[test]
+    fn test_remote_url_prefers_origin() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let repo = temp.path().join("repo");
+        create_repo(repo.clone(), true);
+        fs::write(
+            repo.join(".git/config"),
+            "[core]\n\tbare = false\n\
+             [remote \"upstream\"]\n\turl = git@gitlab.internal:org/repo.git\n\
+             [remote \"origin\"]\n\turl = https://github.com/me/repo.git\n\
+             \tfetch = +refs/heads/*:refs/remotes/origin/*\n",
+        )
+        .expect("write config");
+        assert_eq!(
+            remote_url(&repo).as_deref(),
+            Some("https://github.com/me/repo.git")
+        );
+    }
+
+    #[test]
+    fn test_remote_url_falls_back_to_first_remote() {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let repo = temp.path().join("repo");
+        create_repo(repo.clone(), true);
+        fs::write(
+            repo.join(".git/config"),
+            "[remote \"upstream\"]\n\turl = git@gitlab.internal:org/repo.git\n",
+        )
+        .expect("write config");
+        assert_eq!(
+            remote_url(&repo).as_deref(),
+            Some("git@gitlab.internal:org/repo.git")
+        );
+
+        let no_remotes = temp.path().join("no-remotes");
+        create_repo(no_remotes.clone(), true);
+        assert_eq!(remote_url(&no_remotes), None);
+    }
+
+    fn create_bare_repo(path: &Path) {
+        fs::create_dir_all(path.join("objects")).unwrap();
+        fs::create_dir_all(path.join("refs")).unwrap();
+        fs::write(path.join("HEAD"), "ref: refs/heads/main\n").unwrap();
+    }
+
+    /// Lay out a linked worktree the way `git worktree add` does
+    fn create_worktree(main: &Path, worktree: &Path) {
+        let name = worktree.file_name().unwrap();
+        let admin = main.join(".git/worktrees").join(name);
+        fs::create_dir_all(&admin).unwrap();
+        fs::write(admin.join("commondir"), "../..\n").unwrap();
+        fs::write(admin.join("HEAD"), "ref: refs/heads/feature\n").unwrap();
+        fs::create_dir_all(worktree).unwrap();
+        fs::write(
+            worktree.join(".git"),
+            format!("gitdir: {}\n", admin.display()),
+        )
+        .unwrap();
+    }
+
     fn create_repo(path: PathBuf, git_dir: bool) {
         fs::create_dir_all(&path).expect("create repo dir");
         let git_path = path.join(".git");
@@ -0,0 +1,139 @@
+//! Retrying git commands that fail with transient network errors.
+
+use std::process::Output;
+use std::time::Duration;
+
+/// stderr fragments (matched case-insensitively) that mark a failure as
+/// transient: dropped SSH handshakes, DNS hiccups and flaky HTTP gateways.
+pub const DEFAULT_TRANSIENT_PATTERNS: &[&str] = &[
+    "connection reset by peer",
+    "kex_exchange_identification",
+    "ssh_exchange_identification",
+    "connection closed by remote host",
+    "connection timed out",
+    "operation timed out",
+    "could not resolve host",
+    "temporary failure in name resolution",
+    "the remote end hung up unexpectedly",
+    "early eof",
+    "rpc failed",
+    "returned error: 502",
+    "returned error: 503",
+    "returned error: 504",
+];
+
+/// Delay before the first retry; doubles for each later retry
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// When and how often to re-run a repo's git command
+#[derive(Clone, Debug, Default)]
+pub struct RetryPolicy {
+    /// Extra attempts allowed after the first run
+    pub retries: u32,
+    /// Lowercased stderr fragments that make a failure retryable
+    patterns: Vec<String>,
+}
+
+impl RetryPolicy {
+    /// Policy using the built-in transient patterns plus `extra_patterns`
+    pub fn new(retries: u32, extra_patterns: &[String]) -> Self {
+        let patterns = DEFAULT_TRANSIENT_PATTERNS
+            .iter()
+            .map(|pattern| pattern.to_string())
+            .chain(extra_patterns.iter().map(|pattern| pattern.to_lowercase()))
+            .collect();
+        RetryPolicy { retries, patterns }
+    }
+
+    /// Whether a failed run that has used `attempts` attempts should run again
+    pub fn should_retry(&self, attempts: u32, output: &Output) -> bool {
+        attempts <= self.retries && !output.status.success() && self.is_transient(&output.stderr)
+    }
+
+    fn is_transient(&self, stderr: &[u8]) -> bool {
+        let stderr = String::from_utf8_lossy(stderr).to_lowercase();
+        self.patterns
+            .iter()
+            .any(|pattern| stderr.contains(pattern.as_str()))
+    }
+}
+
+/// Exponential backoff before retry number `retry` (1-based)
+pub fn backoff(retry: u32) -> Duration {
+    let factor = 1u32
+        .checked_shl(retry.saturating_sub(1))
+        .unwrap_or(u32::MAX);
+    BASE_BACKOFF.saturating_mul(factor).min(MAX_BACKOFF)
+}
+
+/// Suffix appended to a repo's message when it needed retries, e.g. ` (after 2 retries)`
+pub fn retry_suffix(attempts: u32) -> String {
+    match attempts.saturating_sub(1) {
+        0 => String::new(),
+        1 => " (after 1 retry)".to_string(),
+        retries => format!(" (after {} retries)", retries),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    fn make_output(stderr: &str, success: bool) -> Output {
+        Output {
+            status: ExitStatus::from_raw(if success { 0 } else { 256 }),
+            stdout: Vec::new(),
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_should_retry_transient_failures_only() {
+        let policy = RetryPolicy::new(2, &[]);
+        let reset = make_output(
+            "Read from remote host github.com: Connection reset by peer",
+            false,
+        );
+        let kex = make_output("kex_exchange_identification: read: Connection reset", false);
+        let denied = make_output("ERROR: Permission to foo/bar.git denied", false);
+
+        assert!(policy.should_retry(1, &reset));
+        assert!(policy.should_retry(2, &kex));
+        assert!(!policy.should_retry(1, &denied));
+        assert!(!policy.should_retry(1, &make_output("connection reset by peer", true)));
+    }
+
+    #[test]
+    fn test_should_retry_stops_after_retry_budget() {
+        let policy = RetryPolicy::new(2, &[]);
+        let reset = make_output("Connection reset by peer", false);
+        assert!(policy.should_retry(2, &reset));
+        assert!(!policy.should_retry(3, &reset));
+        assert!(!RetryPolicy::new(0, &[]).should_retry(1, &reset));
+    }
+
+    #[test]
+    fn test_extra_patterns_are_case_insensitive() {
+        let policy = RetryPolicy::new(1, &["Server Busy".to_string()]);
+        assert!(policy.should_retry(1, &make_output("remote: SERVER BUSY, try later", false)));
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        assert_eq!(backoff(1), Duration::from_millis(500));
+        assert_eq!(backoff(2), Duration::from_secs(1));
+        assert_eq!(backoff(3), Duration::from_secs(2));
+        assert_eq!(backoff(10), MAX_BACKOFF);
+        assert_eq!(backoff(64), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_retry_suffix() {
+        assert_eq!(retry_suffix(1), "");
+        assert_eq!(retry_suffix(2), " (after 1 retry)");
+        assert_eq!(retry_suffix(3), " (after 2 retries)");
+    }
+}
@@ -0,0 +1,262 @@
+//! `git-all-roots`: run across registered root directories from anywhere.
+//!
+//! Roots live in `~/.config/git-all/roots.toml`. The same binary runs in roots
+//! mode when invoked through a name containing "roots", usually a
+//! `git-all-roots -> git-all` symlink.
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::RootsAction;
+use crate::config::user_config_dir;
+use crate::repo::{DiscoveryOptions, ScanDepth, discover_repos};
+
+/// Registry file, next to the user `config.toml`
+pub const ROOTS_FILE: &str = "roots.toml";
+
+/// True when the binary was invoked as `git-all-roots` (or any name containing "roots")
+pub fn is_roots_invocation() -> bool {
+    std::env::args_os()
+        .next()
+        .as_deref()
+        .map(Path::new)
+        .and_then(Path::file_name)
+        .is_some_and(|name| name.to_string_lossy().contains("roots"))
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct RootsFile {
+    #[serde(default)]
+    roots: Vec<RootEntry>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct RootEntry {
+    path: PathBuf,
+}
+
+/// The persistent list of roots, in the order they were added
+#[derive(Debug)]
+pub struct Registry {
+    path: PathBuf,
+    roots: Vec<PathBuf>,
+}
+
+impl Registry {
+    /// Load the registry from the user config directory
+    pub fn load_default() -> Result<Self> {
+        let dir =
+            user_config_dir().context("cannot locate the config directory: $HOME is not set")?;
+        Self::load(dir.join(ROOTS_FILE))
+    }
+
+    pub fn load(path: PathBuf) -> Result<Self> {
+        let roots = match fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str::<RootsFile>(&contents)
+                .with_context(|| format!("invalid roots file {}", path.display()))?
+                .roots
+                .into_iter()
+                .map(|entry| entry.path)
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+        };
+        Ok(Registry { path, roots })
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Register `dir` (canonicalized). Returns false if it was already a root.
+    pub fn add(&mut self, dir: &Path) -> Result<bool> {
+        let dir = dir
+            .canonicalize()
+            .with_context(|| format!("cannot add root {}", dir.display()))?;
+        if !dir.is_dir() {
+            bail!("cannot add root {}: not a directory", dir.display());
+        }
+        if self.roots.contains(&dir) {
+            return Ok(false);
+        }
+        self.roots.push(dir);
+        Ok(true)
+    }
+
+    /// Unregister `dir`, which may no longer exist on disk. Returns the removed root.
+    pub fn remove(&mut self, dir: &Path, cwd: &Path) -> Result<PathBuf> {
+        let absolute = cwd.join(dir);
+        let canonical = absolute.canonicalize().ok();
+        let position = self
+            .roots
+            .iter()
+            .position(|root| *root == absolute || Some(root) == canonical.as_ref())
+            .with_context(|| format!("{} is not a registered root", dir.display()))?;
+        Ok(self.roots.remove(position))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let file = RootsFile {
+            roots: self
+                .roots
+                .iter()
+                .map(|path| RootEntry { path: path.clone() })
+                .collect(),
+        };
+        fs::write(&self.path, toml::to_string(&file)?)
+            .with_context(|| format!("failed to write {}", self.path.display()))
+    }
+}
+
+/// `git-all-roots roots [list|add|rm]`
+pub fn run(action: &RootsAction, cwd: &Path) -> Result<()> {
+    let mut registry = Registry::load_default()?;
+    match action {
+        RootsAction::List => {
+            if registry.roots().is_empty() {
+                println!("No roots registered. Add one with: git-all-roots roots add <path>");
+            }
+            for root in registry.roots() {
+                println!("{}", root_label(root));
+            }
+        }
+        RootsAction::Add { path } => {
+            if registry.add(&cwd.join(path))? {
+                registry.save()?;
+                let added = registry.roots().last().expect("root was just added");
+                println!("Added root {}", root_label(added));
+            } else {
+                println!("Already a root: {}", path.display());
+            }
+        }
+        RootsAction::Rm { path } => {
+            let removed = registry.remove(path, cwd)?;
+            registry.save()?;
+            println!("Removed root {}", root_label(&removed));
+        }
+    }
+    Ok(())
+}
+
+/// Discover repos under every root, in registry order. A repo under nested
+/// roots is listed once, under the deepest one. Missing roots are skipped
+/// with a warning.
+pub fn find_git_repos_in_roots(
+    roots: &[PathBuf],
+    depth: ScanDepth,
+    options: &DiscoveryOptions,
+) -> Result<Vec<PathBuf>> {
+    let mut repos = Vec::new();
+    for root in roots {
+        if !root.is_dir() {
+            eprintln!("git-all-roots: skipping missing root {}", root_label(root));
+            continue;
+        }
+        repos.extend(
+            discover_repos(root, depth, options)?
+                .into_iter()
+                .filter(|repo| owning_root(repo, roots) == Some(root)),
+        );
+    }
+    Ok(repos)
+}
+
+/// The root a repo belongs to: the longest registered root containing it
+pub fn owning_root<'a>(repo: &Path, roots: &'a [PathBuf]) -> Option<&'a PathBuf> {
+    roots
+        .iter()
+        .filter(|root| repo.starts_with(root))
+        .max_by_key(|root| root.components().count())
+}
+
+/// Short label for a root, with the home directory shown as `~`
+pub fn root_label(root: &Path) -> String {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    match home
+        .as_deref()
+        .and_then(|home| root.strip_prefix(home).ok())
+    {
+        Some(relative) if relative.as_os_str().is_empty() => "~".to_string(),
+        Some(relative) => format!("~/{}", relative.display()),
+        None => root.display().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry_round_trip() {
+        let temp = tempfile::tempdir().unwrap();
+        let src = temp.path().join("src");
+        let work = temp.path().join("work");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&work).unwrap();
+        let file = temp.path().join("config/roots.toml");
+
+        let mut registry = Registry::load(file.clone()).unwrap();
+        assert!(registry.roots().is_empty());
+        assert!(registry.add(&src).unwrap());
+        assert!(registry.add(&work.join("../work")).unwrap());
+        assert!(!registry.add(&src).unwrap());
+        registry.save().unwrap();
+
+        let registry = Registry::load(file).unwrap();
+        assert_eq!(
+            registry.roots(),
+            [src.canonicalize().unwrap(), work.canonicalize().unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_remove_root_that_no_longer_exists() {
+        let temp = tempfile::tempdir().unwrap();
+        let gone = temp.path().canonicalize().unwrap().join("gone");
+        fs::create_dir_all(&gone).unwrap();
+
+        let mut registry = Registry::load(temp.path().join("roots.toml")).unwrap();
+        registry.add(&gone).unwrap();
+        fs::remove_dir(&gone).unwrap();
+
+        assert!(
+            registry
+                .remove(Path::new("elsewhere"), temp.path())
+                .is_err()
+        );
+        assert_eq!(registry.remove(&gone, temp.path()).unwrap(), gone);
+        assert!(registry.roots().is_empty());
+    }
+
+    #[test]
+    fn test_add_rejects_missing_directory() {
+        let temp = tempfile::tempdir().unwrap();
+        let mut registry = Registry::load(temp.path().join("roots.toml")).unwrap();
+        assert!(registry.add(&temp.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_owning_root_prefers_the_deepest_root() {
+        let roots = vec![
+            PathBuf::from("/home/me/src"),
+            PathBuf::from("/home/me/src/work"),
+        ];
+        assert_eq!(
+            owning_root(Path::new("/home/me/src/work/app"), &roots),
+            Some(&roots[1])
+        );
+        assert_eq!(
+            owning_root(Path::new("/home/me/src/cli"), &roots),
+            Some(&roots[0])
+        );
+        assert_eq!(owning_root(Path::new("/home/me/srcs/cli"), &roots), None);
+    }
+}
@@ -1,12 +1,25 @@
 use anyhow::Result;
+use serde_json::{Map, Value};
+use std::io::{ErrorKind, Read};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Output, Stdio};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::thread::JoinHandle;
+
+use std::collections::HashMap;
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
 use std::sync::mpsc;
-use std::sync::{Arc, Condvar, Mutex};
-use std::time::Instant;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
 
-use crate::repo::repo_display_name;
+use crate::cancel;
+use crate::printer::{self, Completion, OutputFormat, Row};
+use crate::repo::{current_branch, remote_host, remote_url, repo_display_name};
+use crate::retry::{self, RetryPolicy};
+use crate::roots::owning_root;
+use crate::ssh_pool::{self, PoolSize, SshPool};
 use crate::trace::{RepoTraceSample, TraceSink};
+use crate::url_rewrite::{RewriteHost, rewrite_configs, ssh_endpoint};
 
 /// Simple counting semaphore using stdlib primitives.
 /// Allows limiting concurrent operations to N at a time.
@@ -24,12 +37,20 @@ impl Semaphore {
     }
 
     /// Acquire a permit, blocking if none available.
-    fn acquire(&self) {
+    /// Returns false without a permit once Ctrl-C has been pressed.
+    fn acquire(&self) -> bool {
         let mut count = self.count.lock().unwrap();
         while *count == 0 {
-            count = self.cond.wait(count).unwrap();
+            if cancel::requested() {
+                return false;
+            }
+            count = self.cond.wait_timeout(count, CANCEL_POLL).unwrap().0;
+        }
+        if cancel::requested() {
+            return false;
         }
         *count -= 1;
+        true
     }
 
     /// Release a permit, waking one waiting thread.
@@ -40,11 +61,46 @@ impl Semaphore {
     }
 }
 
-const MIN_REPO_NAME_WIDTH: usize = 4;
-const MAX_REPO_NAME_WIDTH_CAP: usize = 48;
+/// Permits a worker holds while its repo runs, released on drop
+struct Permits<'a>(Vec<&'a Semaphore>);
+
+impl<'a> Permits<'a> {
+    /// Acquire every semaphore in order (SSH master, then host, then the global
+    /// limit, so a repo waiting on a busy master or host doesn't hold a global
+    /// slot). Returns `None`, holding nothing, once Ctrl-C has been pressed.
+    fn acquire(semaphores: impl IntoIterator<Item = &'a Semaphore>) -> Option<Self> {
+        let mut permits = Permits(Vec::new());
+        for semaphore in semaphores {
+            if !semaphore.acquire() {
+                return None;
+            }
+            permits.0.push(semaphore);
+        }
+        Some(permits)
+    }
+}
+
+impl Drop for Permits<'_> {
+    fn drop(&mut self) {
+        for semaphore in self.0.iter().rev() {
+            semaphore.release();
+        }
+    }
+}
+
+const UNKNOWN_BRANCH: &str = "unknown";
+
+/// git subcommands that connect to a remote
+const NETWORK_SUBCOMMANDS: &[&str] = &["fetch", "pull", "push", "ls-remote", "clone"];
+
+/// How often blocked threads check for Ctrl-C
+const CANCEL_POLL: Duration = Duration::from_millis(50);
+
+/// How long interrupted git processes get to exit after SIGTERM before SIGKILL
+const TERMINATE_GRACE: Duration = Duration::from_secs(2);
 
 /// URL scheme to force for git operations
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum UrlScheme {
     /// Force SSH: git@github.com:user/repo
     Ssh,
@@ -52,85 +108,132 @@ pub enum UrlScheme {
     Https,
 }
 
-/// Format repo name with fixed width: truncate long names, pad short ones
-fn compute_name_width(repos: &[PathBuf], display_root: &Path) -> usize {
-    let mut max_len = 0usize;
-    for repo in repos {
-        let name = repo_display_name(repo, display_root);
-        max_len = max_len.max(name.len());
-    }
-
-    let capped = max_len.min(MAX_REPO_NAME_WIDTH_CAP);
-    capped.max(MIN_REPO_NAME_WIDTH)
-}
-
-/// Format repo name with fixed width: truncate long names, pad short ones
-fn format_repo_name(name: &str, width: usize) -> String {
-    let display_name = if name.len() > width {
-        if width <= 4 {
-            name.chars().take(width).collect()
-        } else {
-            format!("{}-...", &name[..width - 4])
-        }
-    } else {
-        name.to_string()
-    };
-    format!("[{:<width$}]", display_name, width = width)
+/// Progress reported from worker threads back to the run loop
+enum WorkerEvent {
+    /// The repo acquired a worker permit and is about to spawn git
+    Started(usize),
+    /// Ctrl-C arrived before the repo's git command was spawned
+    NotStarted(usize),
+    /// git exited (or failed to spawn), the follow-up's report if one ran,
+    /// how long it ran across all attempts, the attempt count, plus its trace
+    /// sample when tracing
+    Finished(
+        usize,
+        Result<Output, std::io::Error>,
+        Option<Box<Report>>,
+        Duration,
+        u32,
+        Option<RepoTraceSample>,
+    ),
 }
 
 /// Cross-cutting options that apply to every git invocation in a run.
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct GitInvocationOptions {
     pub url_scheme: Option<UrlScheme>,
+    /// Hosts to rewrite besides each repo's remote hosts (`--url-host`)
+    pub url_hosts: Vec<RewriteHost>,
     pub ssh_multiplexing: bool,
+    /// Pre-warmed masters for network commands (`--ssh-masters`); wins over `ssh_multiplexing`
+    pub ssh_pool: Option<PoolSize>,
+    /// Kill git (and its process group) if it runs longer than this
+    pub timeout: Option<Duration>,
+}
+
+/// How many git commands may run at once, overall and per remote host
+#[derive(Clone, Debug, Default)]
+pub struct WorkerLimits {
+    /// Global limit; 0 means unlimited
+    pub workers: usize,
+    /// Limits for network commands, keyed by lowercased remote host
+    pub per_host: HashMap<String, usize>,
+}
+
+/// Parse one `--host-workers` entry such as `github.com=10`
+pub fn parse_host_limit(value: &str) -> Result<(String, usize), String> {
+    let invalid = || format!("invalid host limit: {value}. Use HOST=N, e.g. github.com=10.");
+    let (host, limit) = value.split_once('=').ok_or_else(invalid)?;
+    let host = host.trim().to_lowercase();
+    let limit: usize = limit.trim().parse().map_err(|_| invalid())?;
+    if host.is_empty() || limit == 0 {
+        return Err(invalid());
+    }
+    Ok((host, limit))
 }
 
 /// Execution context holding configuration for running git commands
 pub struct ExecutionContext {
     dry_run: bool,
-    url_scheme: Option<UrlScheme>,
-    ssh_multiplexing: bool,
-    max_connections: usize,
+    invocation: GitInvocationOptions,
+    limits: WorkerLimits,
     display_root: PathBuf,
+    /// Registered roots in `git-all-roots` mode; empty when scanning the cwd
+    roots: Vec<PathBuf>,
+    output_format: OutputFormat,
+    retry: RetryPolicy,
     trace: TraceSink,
 }
 
 impl ExecutionContext {
     pub fn new(
         dry_run: bool,
-        url_scheme: Option<UrlScheme>,
-        ssh_multiplexing: bool,
-        max_connections: usize,
+        invocation: GitInvocationOptions,
+        limits: WorkerLimits,
         display_root: PathBuf,
+        output_format: OutputFormat,
+        retry: RetryPolicy,
         trace: TraceSink,
     ) -> Self {
         Self {
             dry_run,
-            url_scheme,
-            ssh_multiplexing,
-            max_connections,
+            invocation,
+            limits,
             display_root,
+            roots: Vec::new(),
+            output_format,
+            retry,
             trace,
         }
     }
 
+    /// Group results under these roots and name repos relative to them
+    pub fn with_roots(mut self, roots: Vec<PathBuf>) -> Self {
+        self.roots = roots;
+        self
+    }
+
     pub fn is_dry_run(&self) -> bool {
         self.dry_run
     }
 
     pub fn git_invocation_options(&self) -> GitInvocationOptions {
-        GitInvocationOptions {
-            url_scheme: self.url_scheme,
-            ssh_multiplexing: self.ssh_multiplexing,
-        }
+        self.invocation.clone()
     }
 
     pub fn max_connections(&self) -> usize {
-        self.max_connections
+        self.limits.workers
+    }
+
+    pub fn worker_limits(&self) -> &WorkerLimits {
+        &self.limits
     }
 
-    pub fn display_root(&self) -> &std::path::Path {
-        &self.display_root
+    /// The root `repo` is grouped under in `git-all-roots` mode
+    pub fn root_of(&self, repo: &Path) -> Option<&PathBuf> {
+        owning_root(repo, &self.roots)
+    }
+
+    /// Name shown for `repo`: its path relative to its root or the cwd
+    pub fn display_name(&self, repo: &Path) -> String {
+        repo_display_name(repo, self.root_of(repo).unwrap_or(&self.display_root))
+    }
+
+    pub fn output_format(&self) -> OutputFormat {
+        self.output_format
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry
     }
 
     pub fn trace_enabled(&self) -> bool {
@@ -142,159 +245,754 @@ impl ExecutionContext {
     }
 }
 
+/// A repo's message and JSON fields as decided by its follow-up, reported
+/// instead of what the formatter makes of git's output
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    pub message: String,
+    pub fields: Map<String, Value>,
+}
+
+/// What a follow-up step found
+#[derive(Debug)]
+pub enum FollowUpResult {
+    /// Report this for the repo; the main command's output is kept as is
+    Report(Report),
+    /// A git command the follow-up ran failed; its output replaces the main
+    /// command's and goes through the formatter
+    Failed(Output),
+}
+
+/// Runs the git commands of a follow-up step, in the same repo and with the
+/// same spawning, `--timeout` and Ctrl-C handling as the repo's main command
+pub struct FollowUpGit<'a> {
+    idx: usize,
+    repo_path: &'a Path,
+    children: &'a RunningChildren,
+    opts: &'a GitInvocationOptions,
+}
+
+impl FollowUpGit<'_> {
+    /// Run `git <args>` and wait for it. Fails with `ErrorKind::Interrupted`
+    /// once Ctrl-C has been pressed, or `ErrorKind::TimedOut` after `--timeout`.
+    pub fn run(&self, args: &[&str]) -> std::io::Result<Output> {
+        let args = args.iter().map(|arg| arg.to_string()).collect();
+        let cmd = GitCommand::new(self.repo_path.to_path_buf(), args);
+        let Some(spawned) = self.children.spawn(self.idx, &cmd, self.opts) else {
+            return Err(std::io::Error::new(ErrorKind::Interrupted, "interrupted"));
+        };
+        let result = wait_with_timeout(spawned?, self.opts.timeout);
+        self.children.remove(self.idx);
+        result
+    }
+}
+
+/// Local follow-up step run in the worker once a repo's git command succeeds,
+/// after its host and SSH master permits are released. It gets that
+/// command's output and decides what is reported for the repo.
+pub type FollowUpStep = fn(&FollowUpGit, &Output) -> std::io::Result<FollowUpResult>;
+
 /// A git command ready to be executed against a repository
 pub struct GitCommand {
     pub repo_path: PathBuf,
     pub args: Vec<String>,
+    /// Socket of the pooled SSH master this command goes through
+    pub ssh_control_path: Option<PathBuf>,
+    pub follow_up_step: Option<FollowUpStep>,
+    /// Remote this command talks to when `repo_path` has none yet, e.g. for clone
+    pub remote_url: Option<String>,
+    /// Already decided without running git, e.g. a clone whose checkout exists
+    pub settled: Option<Report>,
 }
 
 impl GitCommand {
     pub fn new(repo_path: PathBuf, args: Vec<String>) -> Self {
-        Self { repo_path, args }
+        Self {
+            repo_path,
+            args,
+            ssh_control_path: None,
+            follow_up_step: None,
+            remote_url: None,
+            settled: None,
+        }
     }
 
-    /// Spawn the git command without waiting for completion.
-    /// Returns immediately with a Child process handle.
-    pub fn spawn(&self, opts: GitInvocationOptions) -> std::io::Result<std::process::Child> {
-        let mut cmd = Command::new("git");
+    /// A repo that needs no git command; it is reported as succeeded with
+    /// `report` and never takes a worker
+    pub fn settled(repo_path: PathBuf, report: Report) -> Self {
+        Self {
+            settled: Some(report),
+            ..Self::new(repo_path, Vec::new())
+        }
+    }
 
-        // Inject URL scheme override if specified (must come before other args)
+    /// Run `step` after this command succeeds and report what it decides
+    /// (not shown in dry-run output)
+    pub fn with_follow_up_step(mut self, step: FollowUpStep) -> Self {
+        self.follow_up_step = Some(step);
+        self
+    }
+
+    /// Apply per-host limits and pooled SSH masters by `url` instead of the repo's remote
+    pub fn with_remote_url(mut self, url: String) -> Self {
+        self.remote_url = Some(url);
+        self
+    }
+
+    /// The remote URL host limits and SSH masters are keyed by
+    fn network_url(&self, repo: &Path) -> Option<String> {
+        self.remote_url.clone().or_else(|| remote_url(repo))
+    }
+
+    /// `-c` config overrides injected ahead of every git invocation.
+    fn config_args(&self, opts: &GitInvocationOptions) -> Vec<String> {
+        let mut args = Vec::new();
+
+        // Inject URL scheme overrides for this repo's hosts (must come before other args)
         if let Some(scheme) = opts.url_scheme {
-            match scheme {
-                UrlScheme::Ssh => {
-                    cmd.arg("-c")
-                        .arg("url.git@github.com:.insteadOf=https://github.com/");
-                }
-                UrlScheme::Https => {
-                    cmd.arg("-c")
-                        .arg("url.https://github.com/.insteadOf=git@github.com:");
-                }
+            for rewrite in rewrite_configs(&self.repo_path, scheme, &opts.url_hosts) {
+                args.push("-c".to_string());
+                args.push(rewrite);
             }
         }
 
-        if !opts.ssh_multiplexing {
-            cmd.arg("-c")
-                .arg("core.sshCommand=ssh -o ControlMaster=no -o ControlPath=none");
+        if let Some(control_path) = &self.ssh_control_path {
+            args.push("-c".to_string());
+            args.push(format!(
+                "core.sshCommand={}",
+                ssh_pool::ssh_command(control_path)
+            ));
+        } else if !opts.ssh_multiplexing || opts.ssh_pool.is_some() {
+            args.push("-c".to_string());
+            args.push("core.sshCommand=ssh -o ControlMaster=no -o ControlPath=none".to_string());
         }
 
-        cmd.arg("-C")
+        args
+    }
+
+    /// Whether this git subcommand talks to a remote, and so is subject to per-host limits
+    pub fn uses_network(&self) -> bool {
+        self.args
+            .first()
+            .is_some_and(|subcommand| NETWORK_SUBCOMMANDS.contains(&subcommand.as_str()))
+    }
+
+    /// Full argv, starting with `git`, exactly as `spawn` will run it
+    pub fn argv(&self, opts: &GitInvocationOptions) -> Vec<String> {
+        let mut argv = vec!["git".to_string()];
+        argv.extend(self.config_args(opts));
+        argv.push("-C".to_string());
+        argv.push(self.repo_path.to_string_lossy().into_owned());
+        argv.extend(self.args.iter().cloned());
+        argv
+    }
+
+    /// Spawn the git command without waiting for completion.
+    /// Returns immediately with a Child process handle.
+    pub fn spawn(&self, opts: &GitInvocationOptions) -> std::io::Result<std::process::Child> {
+        let mut cmd = Command::new("git");
+        cmd.args(self.config_args(opts))
+            .arg("-C")
             .arg(&self.repo_path)
             .args(&self.args)
             .stdin(Stdio::null())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
-            .env("GIT_TERMINAL_PROMPT", "0")
-            .spawn()
+            .env("GIT_TERMINAL_PROMPT", "0");
+
+        // Own session, and so own process group, so timeouts and Ctrl-C reach
+        // ssh/credential helpers too and the terminal's SIGINT goes to git-all
+        // alone. Without a controlling terminal ssh can't prompt for a
+        // passphrase or host key (it would stop on SIGTTIN from a background
+        // group); it fails right away instead, like `BatchMode=yes`.
+        #[cfg(unix)]
+        // SAFETY: setsid(2) is async-signal-safe and touches no parent state
+        unsafe {
+            cmd.pre_exec(|| {
+                if libc::setsid() == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+
+        cmd.spawn()
     }
 
     /// Build the full command string for display (used in dry-run)
-    pub fn command_string(&self, opts: GitInvocationOptions) -> String {
-        let scheme_args = match opts.url_scheme {
-            Some(UrlScheme::Ssh) => "-c \"url.git@github.com:.insteadOf=https://github.com/\" ",
-            Some(UrlScheme::Https) => "-c \"url.https://github.com/.insteadOf=git@github.com:\" ",
-            None => "",
-        };
-        let ssh_args = if opts.ssh_multiplexing {
-            ""
-        } else {
-            "-c \"core.sshCommand=ssh -o ControlMaster=no -o ControlPath=none\" "
-        };
-        format!(
-            "git {}{}-C {} {}",
-            scheme_args,
-            ssh_args,
-            self.repo_path.display(),
-            self.args.join(" ")
-        )
+    pub fn command_string(&self, opts: &GitInvocationOptions) -> String {
+        let mut parts = Vec::new();
+        let mut quote_next = false;
+        for arg in self.argv(opts) {
+            if quote_next {
+                parts.push(format!("\"{}\"", arg));
+            } else {
+                parts.push(arg.clone());
+            }
+            quote_next = arg == "-c";
+        }
+        parts.join(" ")
+    }
+}
+
+/// Wait for a child to exit, killing its process group once `timeout` elapses.
+/// A killed child yields an `ErrorKind::TimedOut` error.
+fn wait_with_timeout(mut child: Child, timeout: Option<Duration>) -> std::io::Result<Output> {
+    let Some(timeout) = timeout else {
+        return child.wait_with_output();
+    };
+
+    // Drain pipes on their own threads, mirroring wait_with_output()
+    let stdout_reader = child.stdout.take().map(spawn_pipe_reader);
+    let stderr_reader = child.stderr.take().map(spawn_pipe_reader);
+
+    let pid = child.id();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(child.wait());
+    });
+
+    let status = match rx.recv_timeout(timeout) {
+        Ok(status) => status?,
+        Err(_) => {
+            signal_process_group(pid, true);
+            // Reap the child so it doesn't linger as a zombie
+            let _ = rx.recv();
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("timed out after {}", format_duration(timeout)),
+            ));
+        }
+    };
+
+    Ok(Output {
+        status,
+        stdout: join_pipe_reader(stdout_reader),
+        stderr: join_pipe_reader(stderr_reader),
+    })
+}
+
+fn spawn_pipe_reader<R: Read + Send + 'static>(mut pipe: R) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = pipe.read_to_end(&mut buf);
+        buf
+    })
+}
+
+fn join_pipe_reader(reader: Option<JoinHandle<Vec<u8>>>) -> Vec<u8> {
+    reader
+        .and_then(|handle| handle.join().ok())
+        .unwrap_or_default()
+}
+
+/// What a command killed by `--timeout` reports: a failed status with the
+/// timeout as its stderr, so formatters treat it like any other git failure
+fn timed_out_output(err: &std::io::Error) -> Output {
+    Output {
+        status: killed_status(),
+        stdout: Vec::new(),
+        stderr: format!("{err}\n").into_bytes(),
+    }
+}
+
+#[cfg(unix)]
+fn killed_status() -> ExitStatus {
+    use std::os::unix::process::ExitStatusExt;
+    ExitStatus::from_raw(libc::SIGKILL)
+}
+
+#[cfg(not(unix))]
+fn killed_status() -> ExitStatus {
+    use std::os::windows::process::ExitStatusExt;
+    ExitStatus::from_raw(1)
+}
+
+/// Send SIGTERM (or SIGKILL when `force`) to a git child's process group
+#[cfg(unix)]
+fn signal_process_group(pid: u32, force: bool) {
+    let signal = if force { libc::SIGKILL } else { libc::SIGTERM };
+    // SAFETY: kill(2) has no memory-safety preconditions; a negative pid
+    // targets the process group `setsid()` created in spawn.
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), signal);
+    }
+}
+
+#[cfg(not(unix))]
+fn signal_process_group(pid: u32, force: bool) {
+    let pid = pid.to_string();
+    let mut args = vec!["/T", "/PID", pid.as_str()];
+    if force {
+        args.insert(0, "/F");
+    }
+    let _ = Command::new("taskkill")
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// Process ids of git children currently running, keyed by repo index
+#[derive(Default)]
+struct RunningChildren {
+    pids: Mutex<HashMap<usize, u32>>,
+}
+
+impl RunningChildren {
+    /// Spawn `cmd` unless Ctrl-C has been pressed. Spawning under the lock means
+    /// every child is either registered before `signal_all` runs or never started.
+    fn spawn(
+        &self,
+        idx: usize,
+        cmd: &GitCommand,
+        opts: &GitInvocationOptions,
+    ) -> Option<std::io::Result<Child>> {
+        let mut pids = self.pids.lock().unwrap();
+        if cancel::requested() {
+            return None;
+        }
+        let result = cmd.spawn(opts);
+        if let Ok(child) = &result {
+            pids.insert(idx, child.id());
+        }
+        Some(result)
+    }
+
+    fn remove(&self, idx: usize) {
+        self.pids.lock().unwrap().remove(&idx);
+    }
+
+    fn signal_all(&self, force: bool) {
+        for &pid in self.pids.lock().unwrap().values() {
+            signal_process_group(pid, force);
+        }
+    }
+}
+
+/// Sleep for `duration`, waking early if Ctrl-C is pressed.
+/// Returns false when the sleep was cut short by cancellation.
+fn sleep_unless_cancelled(duration: Duration) -> bool {
+    let deadline = Instant::now() + duration;
+    while !cancel::requested() {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return true;
+        }
+        std::thread::sleep(remaining.min(CANCEL_POLL));
+    }
+    false
+}
+
+/// Parse a timeout such as `30s`, `500ms`, `2m` or `1h`. A bare number is seconds.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let normalized = value.trim();
+    let split_at = normalized
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(normalized.len());
+    let (number, unit) = normalized.split_at(split_at);
+
+    let amount: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration: {value}. Use e.g. 30s, 500ms, 2m or 1h."))?;
+    let duration = match unit {
+        "" | "s" => Duration::from_secs(amount),
+        "ms" => Duration::from_millis(amount),
+        "m" => Duration::from_secs(amount * 60),
+        "h" => Duration::from_secs(amount * 60 * 60),
+        _ => {
+            return Err(format!(
+                "invalid duration unit in {value}. Use ms, s, m or h."
+            ));
+        }
+    };
+
+    if duration.is_zero() {
+        return Err("duration must be greater than zero".to_string());
+    }
+
+    Ok(duration)
+}
+
+/// Format a duration the way `parse_duration` accepts it, e.g. `30s` or `1500ms`
+pub fn format_duration(duration: Duration) -> String {
+    if duration.subsec_millis() == 0 {
+        format!("{}s", duration.as_secs())
+    } else {
+        format!("{}ms", duration.as_millis())
     }
 }
 
 /// Trait for formatting command output into one line
 pub trait OutputFormatter: Sync {
     fn format(&self, output: &Output) -> String;
+
+    /// Print git's output unmodified under a repo label instead of condensing
+    /// it into the message column (SPEC 7.1.2). Used by passthrough commands.
+    fn verbatim(&self) -> bool {
+        false
+    }
+
+    /// Structured fields for JSON output (e.g. status counts). Empty by default.
+    fn fields(&self, _output: &Output) -> Map<String, Value> {
+        Map::new()
+    }
+}
+
+/// How far a repo got before the run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunState {
+    Completed,
+    /// git was running when Ctrl-C was pressed and was terminated
+    Interrupted,
+    /// Ctrl-C was pressed before git was spawned
+    NotStarted,
+}
+
+/// Final result of running a command against one repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoOutcome {
+    pub name: String,
+    pub success: bool,
+    pub state: RunState,
 }
 
-/// Run commands in parallel across all repos with streaming output.
+/// Build the end-of-run failure block (SPEC 3.3.3), or `None` when every repo succeeded.
+pub fn failure_summary(outcomes: &[RepoOutcome]) -> Option<String> {
+    let failed: Vec<&str> = outcomes
+        .iter()
+        .filter(|outcome| !outcome.success)
+        .map(|outcome| outcome.name.as_str())
+        .collect();
+    if failed.is_empty() {
+        return None;
+    }
+
+    Some(format!(
+        "{} of {} {} failed: {}",
+        failed.len(),
+        outcomes.len(),
+        if outcomes.len() == 1 {
+            "repository"
+        } else {
+            "repositories"
+        },
+        failed.join(", ")
+    ))
+}
+
+/// Build the report printed after Ctrl-C: which repos completed, were
+/// interrupted mid-run, or never started.
+pub fn interruption_summary(outcomes: &[RepoOutcome]) -> String {
+    let names_in = |state: RunState| -> Vec<&str> {
+        outcomes
+            .iter()
+            .filter(|outcome| outcome.state == state)
+            .map(|outcome| outcome.name.as_str())
+            .collect()
+    };
+    let groups = [
+        ("completed", names_in(RunState::Completed)),
+        ("interrupted", names_in(RunState::Interrupted)),
+        ("never started", names_in(RunState::NotStarted)),
+    ];
+
+    let mut lines = vec![format!(
+        "Interrupted: {}",
+        groups
+            .iter()
+            .map(|(label, names)| format!("{} {}", names.len(), label))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )];
+    for (label, names) in &groups {
+        if !names.is_empty() {
+            lines.push(format!("  {}: {}", label, names.join(", ")));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Plan pooled SSH masters for the network commands among `commands`
+fn plan_ssh_pool(
+    repos: &[PathBuf],
+    commands: &[GitCommand],
+    opts: &GitInvocationOptions,
+) -> Option<SshPool> {
+    let size = opts.ssh_pool?;
+    let endpoints: Vec<_> = repos
+        .iter()
+        .zip(commands)
+        .map(|(repo, cmd)| {
+            if !cmd.uses_network() {
+                return None;
+            }
+            cmd.network_url(repo)
+                .and_then(|url| ssh_endpoint(&url, opts.url_scheme, &opts.url_hosts))
+        })
+        .collect();
+    SshPool::plan(&endpoints, size)
+}
+
+/// Point each command at its pooled master, if it has one
+fn assign_ssh_masters(commands: &mut [GitCommand], pool: Option<&SshPool>) {
+    for (idx, cmd) in commands.iter_mut().enumerate() {
+        cmd.ssh_control_path = pool
+            .and_then(|pool| pool.control_path(idx))
+            .map(Path::to_path_buf);
+    }
+}
+
+/// Run commands in parallel across all repos, reporting progress to a printer.
+///
+/// Execution lives here; presentation lives in `printer`. Non-TTY runs print
+/// in alphabetical order (repos are pre-sorted) with head-of-line blocking,
+/// while interactive terminals get a live table updated as results arrive.
 ///
-/// Results are printed in alphabetical order (repos are pre-sorted) as soon as
-/// contiguous results are available. Uses head-of-line blocking: if repo "aaa"
-/// is slow, "bbb" and "ccc" won't print until "aaa" completes.
+/// Each repo runs on its own worker thread, which waits on git with
+/// `wait_with_timeout` (stdout and stderr are drained concurrently, so it
+/// can't deadlock) and kills git's process group once `--timeout` elapses.
 ///
-/// Uses thread-per-process pattern with `wait_with_output()` which is deadlock-safe
-/// (stdlib internally spawns threads to drain stdout/stderr concurrently).
+/// On Ctrl-C, queued repos are not started and running git process groups get
+/// SIGTERM, then SIGKILL after `TERMINATE_GRACE` (or a second Ctrl-C).
+///
+/// Returns one outcome per repo in discovery order; dry runs return no outcomes.
 pub fn run_parallel<F>(
     ctx: &mut ExecutionContext,
     repos: &[PathBuf],
     build_command: F,
     formatter: &dyn OutputFormatter,
-) -> Result<()>
+) -> Result<Vec<RepoOutcome>>
 where
     F: Fn(&PathBuf) -> GitCommand + Sync,
 {
-    let opts = ctx.git_invocation_options();
+    let opts = &ctx.git_invocation_options();
     let trace_enabled = ctx.trace_enabled();
+    let retry = &ctx.retry_policy().clone();
+
+    let mut commands: Vec<GitCommand> = repos.iter().map(&build_command).collect();
+    let mut pool = plan_ssh_pool(repos, &commands, opts);
 
     if ctx.is_dry_run() {
-        for repo in repos {
-            let cmd = build_command(repo);
+        if let Some(pool) = &pool {
+            for master in pool.master_commands() {
+                println!("{master}");
+            }
+        }
+        assign_ssh_masters(&mut commands, pool.as_ref());
+        for cmd in commands.iter().filter(|cmd| cmd.settled.is_none()) {
             println!("{}", cmd.command_string(opts));
         }
-        return Ok(());
+        return Ok(Vec::new());
     }
 
-    let name_width = compute_name_width(repos, ctx.display_root());
+    // Masters are up before the fan-out and closed when `pool` drops
+    if let Some(pool) = &mut pool {
+        pool.start();
+    }
+    assign_ssh_masters(&mut commands, pool.as_ref());
+    let rows: Vec<Row> = repos
+        .iter()
+        .zip(&commands)
+        .map(|(repo, cmd)| {
+            Row::new(
+                ctx.display_name(repo),
+                current_branch(repo).unwrap_or_else(|| UNKNOWN_BRANCH.to_string()),
+                repo.clone(),
+                if cmd.settled.is_some() {
+                    Vec::new()
+                } else {
+                    cmd.argv(opts)
+                },
+            )
+            .with_root(ctx.root_of(repo).cloned())
+        })
+        .collect();
+    let names: Vec<String> = rows.iter().map(|row| row.name.clone()).collect();
+    let mut printer = printer::select(rows, formatter.verbatim(), ctx.output_format())?;
     let run_started_at = Instant::now();
 
     let max_workers = ctx.max_connections();
 
     let semaphore = if max_workers > 0 && max_workers < repos.len() {
-        Some(Arc::new(Semaphore::new(max_workers)))
+        Some(Semaphore::new(max_workers))
     } else {
         None
     };
 
-    let mut results: Vec<
-        Option<(
-            PathBuf,
-            Result<Output, std::io::Error>,
-            Option<RepoTraceSample>,
-        )>,
-    > = (0..repos.len()).map(|_| None).collect();
-    let mut next_to_print: usize = 0;
+    // Per-host limits only apply to commands that talk to the remote
+    let host_semaphores: HashMap<String, Semaphore> = ctx
+        .worker_limits()
+        .per_host
+        .iter()
+        .map(|(host, &limit)| (host.clone(), Semaphore::new(limit)))
+        .collect();
+    let master_semaphores: Vec<Semaphore> = pool
+        .as_ref()
+        .map(|pool| {
+            (0..pool.len())
+                .map(|_| Semaphore::new(pool.sessions()))
+                .collect()
+        })
+        .unwrap_or_default();
+    let repo_hosts: Vec<Option<String>> = repos
+        .iter()
+        .zip(&commands)
+        .map(|(repo, cmd)| {
+            if host_semaphores.is_empty() || !cmd.uses_network() {
+                return None;
+            }
+            cmd.network_url(repo).and_then(|url| remote_host(&url))
+        })
+        .collect();
+
+    let mut samples: Vec<Option<RepoTraceSample>> = vec![None; repos.len()];
+    let mut successes: Vec<bool> = vec![false; repos.len()];
+    let mut states: Vec<Option<RunState>> = vec![None; repos.len()];
+    let mut running: Vec<bool> = vec![false; repos.len()];
+    let children = RunningChildren::default();
+    // Set on the first Ctrl-C: when running children get SIGKILL if still alive
+    let mut kill_deadline: Option<Instant> = None;
+    let mut killed = false;
     let mut first_exit_ms: Option<u128> = None;
     let mut first_print_ms: Option<u128> = None;
     let mut delayed_repos: usize = 0;
     let mut max_ordered_wait_ms: u128 = 0;
 
+    // Trace each repo at the moment the printer made it visible
+    let mut emit_printed = |printed: &[usize], samples: &[Option<RepoTraceSample>]| -> Result<()> {
+        for &printed_idx in printed {
+            if let Some(sample) = samples[printed_idx] {
+                let printed_ms = run_started_at.elapsed().as_millis();
+                let ordered_wait_ms = sample.ordered_wait_ms(printed_ms);
+                first_exit_ms = Some(
+                    first_exit_ms.map_or(sample.exit_ms, |current| current.min(sample.exit_ms)),
+                );
+                first_print_ms =
+                    Some(first_print_ms.map_or(printed_ms, |current| current.min(printed_ms)));
+                if ordered_wait_ms > 0 {
+                    delayed_repos += 1;
+                }
+                max_ordered_wait_ms = max_ordered_wait_ms.max(ordered_wait_ms);
+                ctx.trace_mut()
+                    .emit_repo(printed_idx, &names[printed_idx], sample, printed_ms)?;
+            }
+        }
+        Ok(())
+    };
+
+    printer.start()?;
+
     let (tx, rx) = mpsc::channel();
 
     std::thread::scope(|s| -> Result<()> {
-        for (idx, repo) in repos.iter().enumerate() {
+        for (idx, mut cmd) in commands.into_iter().enumerate() {
+            if let Some(report) = cmd.settled.take() {
+                let success = Output {
+                    status: ExitStatus::default(),
+                    stdout: Vec::new(),
+                    stderr: Vec::new(),
+                };
+                let _ = tx.send(WorkerEvent::Finished(
+                    idx,
+                    Ok(success),
+                    Some(Box::new(report)),
+                    Duration::ZERO,
+                    0,
+                    None,
+                ));
+                continue;
+            }
             let tx = tx.clone();
-            let cmd = build_command(repo);
-            let repo = repo.clone();
-            let sem = semaphore.clone();
+            let host_semaphore = repo_hosts[idx]
+                .as_deref()
+                .and_then(|host| host_semaphores.get(host));
+            let master_semaphore = pool
+                .as_ref()
+                .and_then(|pool| pool.master_of(idx))
+                .map(|master| &master_semaphores[master]);
+            let network_semaphores = master_semaphore.into_iter().chain(host_semaphore);
+            let children = &children;
+            let semaphore = semaphore.as_ref();
 
             s.spawn(move || {
-                if let Some(ref sem) = sem {
-                    sem.acquire();
-                }
+                // Network limits first, so a repo waiting on a busy master or
+                // host doesn't hold a global slot
+                let Some(network_permits) = Permits::acquire(network_semaphores) else {
+                    let _ = tx.send(WorkerEvent::NotStarted(idx));
+                    return;
+                };
+                let Some(permits) = Permits::acquire(semaphore) else {
+                    let _ = tx.send(WorkerEvent::NotStarted(idx));
+                    return;
+                };
+                let _ = tx.send(WorkerEvent::Started(idx));
+                let started_at = Instant::now();
 
                 let start_ms = if trace_enabled {
                     Some(run_started_at.elapsed().as_millis())
                 } else {
                     None
                 };
-                let spawn_result = cmd.spawn(opts);
-                let spawn_ms = if trace_enabled {
-                    Some(run_started_at.elapsed().as_millis())
-                } else {
-                    None
+                let mut spawn_ms = None;
+                let mut attempts = 0;
+                let mut last_result = None;
+                while let Some(spawn_result) = children.spawn(idx, &cmd, opts) {
+                    attempts += 1;
+                    if trace_enabled && spawn_ms.is_none() {
+                        spawn_ms = Some(run_started_at.elapsed().as_millis());
+                    }
+                    let result = match spawn_result {
+                        Ok(child) => {
+                            let result = wait_with_timeout(child, opts.timeout);
+                            children.remove(idx);
+                            result
+                        }
+                        Err(err) => Err(err),
+                    };
+                    let retry_again = matches!(
+                        &result,
+                        Ok(output) if retry.should_retry(attempts, output)
+                    );
+                    last_result = Some(result);
+                    if !retry_again || !sleep_unless_cancelled(retry::backoff(attempts)) {
+                        break;
+                    }
+                }
+                let Some(mut result) = last_result else {
+                    drop(permits);
+                    drop(network_permits);
+                    let _ = tx.send(WorkerEvent::NotStarted(idx));
+                    return;
                 };
-                let result = match spawn_result {
-                    Ok(child) => child.wait_with_output(),
-                    Err(err) => Err(err),
+                // Follow-up steps are local, so the next repo on this host can start
+                drop(network_permits);
+                let mut report = None;
+                let step = match (&result, cmd.follow_up_step) {
+                    (Ok(output), Some(step)) if output.status.success() && !cancel::requested() => {
+                        let git = FollowUpGit {
+                            idx,
+                            repo_path: &cmd.repo_path,
+                            children,
+                            opts,
+                        };
+                        Some(step(&git, output))
+                    }
+                    _ => None,
                 };
+                match step {
+                    Some(Ok(FollowUpResult::Report(followed))) => {
+                        report = Some(Box::new(followed));
+                    }
+                    Some(Ok(FollowUpResult::Failed(output))) => result = Ok(output),
+                    Some(Err(err)) => result = Err(err),
+                    None => {}
+                }
+                // A timeout is an ordinary failure, reported through the formatter
+                let timed_out = matches!(&result, Err(err) if err.kind() == ErrorKind::TimedOut);
+                if timed_out {
+                    result = result.or_else(|err| Ok(timed_out_output(&err)));
+                }
                 let trace_sample = if trace_enabled {
                     let exit_ms = run_started_at.elapsed().as_millis();
                     Some(match &result {
@@ -305,6 +1003,8 @@ where
                             stdout_bytes: output.stdout.len(),
                             stderr_bytes: output.stderr.len(),
                             success: output.status.success(),
+                            timed_out,
+                            attempts,
                         },
                         Err(_) => RepoTraceSample {
                             start_ms: start_ms.expect("trace enabled start_ms"),
@@ -313,54 +1013,123 @@ where
                             stdout_bytes: 0,
                             stderr_bytes: 0,
                             success: false,
+                            timed_out: false,
+                            attempts,
                         },
                     })
                 } else {
                     None
                 };
 
-                if let Some(ref sem) = sem {
-                    sem.release();
-                }
+                drop(permits);
 
-                let _ = tx.send((idx, repo, result, trace_sample));
+                let duration = started_at.elapsed();
+                let _ = tx.send(WorkerEvent::Finished(
+                    idx,
+                    result,
+                    report,
+                    duration,
+                    attempts,
+                    trace_sample,
+                ));
             });
         }
         drop(tx);
 
-        for (idx, repo, result, trace_sample) in rx {
-            results[idx] = Some((repo, result, trace_sample));
-
-            while next_to_print < results.len() {
-                if let Some((ref repo_path, ref res, sample)) = results[next_to_print] {
-                    print_result(repo_path, res, formatter, ctx.display_root(), name_width);
-                    if let Some(sample) = sample {
-                        let printed_ms = run_started_at.elapsed().as_millis();
-                        let ordered_wait_ms = sample.ordered_wait_ms(printed_ms);
-                        let repo_name = repo_display_name(repo_path, ctx.display_root());
-                        first_exit_ms = Some(
-                            first_exit_ms
-                                .map_or(sample.exit_ms, |current| current.min(sample.exit_ms)),
-                        );
-                        first_print_ms = Some(
-                            first_print_ms.map_or(printed_ms, |current| current.min(printed_ms)),
-                        );
-                        if ordered_wait_ms > 0 {
-                            delayed_repos += 1;
+        loop {
+            let event = match rx.recv_timeout(CANCEL_POLL) {
+                Ok(event) => Some(event),
+                Err(mpsc::RecvTimeoutError::Timeout) => None,
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            };
+
+            if cancel::requested() {
+                match kill_deadline {
+                    None => {
+                        // Whatever is running now is reported as interrupted
+                        for (idx, is_running) in running.iter().enumerate() {
+                            if *is_running {
+                                states[idx] = Some(RunState::Interrupted);
+                            }
                         }
-                        max_ordered_wait_ms = max_ordered_wait_ms.max(ordered_wait_ms);
-                        ctx.trace_mut()
-                            .emit_repo(next_to_print, &repo_name, sample, printed_ms)?;
+                        children.signal_all(false);
+                        kill_deadline = Some(Instant::now() + TERMINATE_GRACE);
                     }
-                    next_to_print += 1;
-                } else {
-                    break;
+                    Some(deadline)
+                        if !killed && (cancel::forced() || Instant::now() >= deadline) =>
+                    {
+                        children.signal_all(true);
+                        killed = true;
+                    }
+                    Some(_) => {}
                 }
             }
+
+            let (idx, result, report, duration, attempts, trace_sample) = match event {
+                None => continue,
+                Some(WorkerEvent::Started(idx)) => {
+                    running[idx] = true;
+                    if kill_deadline.is_some() {
+                        states[idx] = Some(RunState::Interrupted);
+                    }
+                    printer.row_started(idx)?;
+                    continue;
+                }
+                Some(WorkerEvent::NotStarted(idx)) => {
+                    states[idx] = Some(RunState::NotStarted);
+                    let result = Err(std::io::Error::new(ErrorKind::Interrupted, "not started"));
+                    (idx, result, None, Duration::ZERO, 0, None)
+                }
+                Some(WorkerEvent::Finished(
+                    idx,
+                    result,
+                    report,
+                    duration,
+                    attempts,
+                    trace_sample,
+                )) => {
+                    running[idx] = false;
+                    (idx, result, report, duration, attempts, trace_sample)
+                }
+            };
+
+            let state = *states[idx].get_or_insert(RunState::Completed);
+            successes[idx] = state == RunState::Completed
+                && matches!(&result, Ok(output) if output.status.success());
+            samples[idx] = trace_sample;
+            let (message, fields) = match (&result, state) {
+                (Err(e), RunState::NotStarted) => (e.to_string(), Map::new()),
+                (_, RunState::Interrupted) => ("interrupted".to_string(), Map::new()),
+                (Ok(output), _) => match report {
+                    Some(report) => (
+                        report.message + &retry::retry_suffix(attempts),
+                        report.fields,
+                    ),
+                    None => (
+                        formatter.format(output) + &retry::retry_suffix(attempts),
+                        formatter.fields(output),
+                    ),
+                },
+                (Err(e), _) => (format!("ERROR: {}", e), Map::new()),
+            };
+            let completion = Completion {
+                message,
+                fields,
+                duration,
+                attempts,
+                branch: current_branch(&repos[idx]),
+                result,
+            };
+
+            let printed = printer.row_finished(idx, completion)?;
+            emit_printed(&printed, &samples)?;
         }
         Ok(())
     })?;
 
+    let printed = printer.finish()?;
+    emit_printed(&printed, &samples)?;
+
     ctx.trace_mut().emit_summary(
         repos.len(),
         first_exit_ms,
@@ -370,66 +1139,172 @@ where
         run_started_at.elapsed().as_millis(),
     )?;
 
-    Ok(())
-}
-
-/// Print result for a single repository
-fn print_result(
-    repo_path: &std::path::Path,
-    result: &Result<Output, std::io::Error>,
-    formatter: &dyn OutputFormatter,
-    display_root: &std::path::Path,
-    name_width: usize,
-) {
-    let name = repo_display_name(repo_path, display_root);
-    let output_line = match result {
-        Ok(output) => {
-            let formatted = formatter.format(output);
-            format!("{} {}", format_repo_name(&name, name_width), formatted)
-        }
-        Err(e) => format!("{} ERROR: {}", format_repo_name(&name, name_width), e),
-    };
-    println!("{}", output_line);
+    Ok(names
+        .into_iter()
+        .zip(successes)
+        .zip(states)
+        .map(|((name, success), state)| RepoOutcome {
+            name,
+            success,
+            state: state.unwrap_or(RunState::NotStarted),
+        })
+        .collect())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn outcome(name: &str, success: bool) -> RepoOutcome {
+        RepoOutcome {
+            name: name.to_string(),
+            success,
+            state: RunState::Completed,
+        }
+    }
+
+    fn outcome_in(name: &str, state: RunState) -> RepoOutcome {
+        RepoOutcome {
+            name: name.to_string(),
+            success: state == RunState::Completed,
+            state,
+        }
+    }
+
     #[test]
-    fn test_format_repo_name_short() {
-        let result = format_repo_name("my-repo", 24);
-        assert_eq!(result, "[my-repo                 ]");
-        assert_eq!(result.len(), 26); // [ + 24 + ]
+    fn test_interruption_summary_lists_each_state() {
+        let outcomes = vec![
+            outcome_in("a", RunState::Completed),
+            outcome_in("b", RunState::Interrupted),
+            outcome_in("c", RunState::NotStarted),
+            outcome_in("d", RunState::NotStarted),
+        ];
+        assert_eq!(
+            interruption_summary(&outcomes),
+            "Interrupted: 1 completed, 1 interrupted, 2 never started\n  \
+             completed: a\n  interrupted: b\n  never started: c, d"
+        );
     }
 
     #[test]
-    fn test_format_repo_name_exact_length() {
-        let result = format_repo_name("exactly-twenty-four-chr", 24);
-        assert_eq!(result.len(), 26);
+    fn test_interruption_summary_omits_empty_groups() {
+        let outcomes = vec![outcome_in("a", RunState::Interrupted)];
+        assert_eq!(
+            interruption_summary(&outcomes),
+            "Interrupted: 0 completed, 1 interrupted, 0 never started\n  interrupted: a"
+        );
     }
 
     #[test]
-    fn test_format_repo_name_truncated() {
-        let result = format_repo_name("this-is-a-very-long-repository-name", 24);
-        assert_eq!(result, "[this-is-a-very-long--...]");
-        assert_eq!(result.len(), 26);
+    fn test_parse_host_limit() {
+        assert_eq!(
+            parse_host_limit("GitHub.com=10"),
+            Ok(("github.com".to_string(), 10))
+        );
+        assert!(parse_host_limit("github.com").is_err());
+        assert!(parse_host_limit("github.com=0").is_err());
+        assert!(parse_host_limit("=4").is_err());
+        assert!(parse_host_limit("github.com=many").is_err());
     }
 
     #[test]
-    fn test_compute_name_width_caps_and_min() {
-        let root = PathBuf::from("/workspace");
-        let repos = vec![
-            root.join("a"),
-            root.join("short"),
-            root.join("this-is-a-very-long-repository-name-that-exceeds-cap"),
-        ];
-        let width = compute_name_width(&repos, &root);
-        assert_eq!(width, MAX_REPO_NAME_WIDTH_CAP);
+    fn test_uses_network() {
+        let cmd = |args: &[&str]| {
+            GitCommand::new(
+                PathBuf::from("/repo"),
+                args.iter().map(|arg| arg.to_string()).collect(),
+            )
+        };
+        assert!(cmd(&["fetch", "--all"]).uses_network());
+        assert!(cmd(&["pull"]).uses_network());
+        assert!(cmd(&["push", "origin"]).uses_network());
+        assert!(!cmd(&["status", "--porcelain"]).uses_network());
+        assert!(!cmd(&[]).uses_network());
+    }
+
+    #[test]
+    fn test_permits_release_on_drop() {
+        let host = Semaphore::new(1);
+        let global = Semaphore::new(2);
+
+        let permits = Permits::acquire([&host, &global]).expect("permits");
+        assert_eq!(*host.count.lock().unwrap(), 0);
+        assert_eq!(*global.count.lock().unwrap(), 1);
+
+        drop(permits);
+        assert_eq!(*host.count.lock().unwrap(), 1);
+        assert_eq!(*global.count.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(3600));
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("10x").is_err());
+        assert!(parse_duration("soon").is_err());
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(30)), "30s");
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1500ms");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_wait_with_timeout_kills_slow_child() {
+        let child = Command::new("sh")
+            .args(["-c", "sleep 10"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .process_group(0)
+            .spawn()
+            .expect("spawn sh");
 
-        let tiny = vec![root.join("a")];
-        let tiny_width = compute_name_width(&tiny, &root);
-        assert_eq!(tiny_width, MIN_REPO_NAME_WIDTH);
+        let started = std::time::Instant::now();
+        let err = wait_with_timeout(child, Some(Duration::from_millis(200)))
+            .expect_err("slow child should time out");
+        assert_eq!(err.kind(), ErrorKind::TimedOut);
+        assert_eq!(err.to_string(), "timed out after 200ms");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_wait_with_timeout_returns_output_when_fast() {
+        let child = Command::new("sh")
+            .args(["-c", "echo out; echo err >&2"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("spawn sh");
+
+        let output = wait_with_timeout(child, Some(Duration::from_secs(5))).expect("fast child");
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+    }
+
+    #[test]
+    fn test_failure_summary_none_when_all_succeed() {
+        let outcomes = vec![outcome("a", true), outcome("b", true)];
+        assert_eq!(failure_summary(&outcomes), None);
+    }
+
+    #[test]
+    fn test_failure_summary_lists_failed_repos_in_order() {
+        let outcomes = vec![
+            outcome("a", false),
+            outcome("b", true),
+            outcome("nested/c", false),
+        ];
+        assert_eq!(
+            failure_summary(&outcomes).as_deref(),
+            Some("2 of 3 repositories failed: a, nested/c")
+        );
     }
 
     /// Test that large output (>64KB) doesn't cause pipe buffer deadlock.
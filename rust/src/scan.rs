@@ -0,0 +1,580 @@
+//! The directory walk behind repository discovery.
+//!
+//! Directories are read in parallel from a shared queue. Well-known heavy
+//! directories (`node_modules`, `target`, ...) are never descended into. With
+//! a cache directory, the result is stored along with the mtime of every
+//! directory scanned or checked for `.git`; a later walk of the same root reuses it when none of
+//! those mtimes changed, which costs one `stat` per directory instead of a
+//! listing.
+//!
+//! Symlinked directories are followed unless `follow_symlinks` is off. A
+//! link back to one of its own ancestors is not followed, and a repo
+//! reachable by several paths is reported once.
+
+use anyhow::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::UNIX_EPOCH;
+
+use crate::filter::{IGNORE_FILE, PathGlobs};
+use crate::repo::is_bare_repo;
+
+/// Directory names skipped by default: dependency and build output trees
+/// that are large and never hold repositories worth running git in
+pub const DEFAULT_PRUNE: &[&str] = &[
+    "node_modules",
+    "bower_components",
+    "target",
+    ".venv",
+    "venv",
+    "__pycache__",
+    ".tox",
+    ".gradle",
+    ".terraform",
+];
+
+/// Upper bound on walker threads; directory reads stop scaling well past this
+const MAX_WALKERS: usize = 16;
+
+/// Bumped whenever the cache file layout changes
+const CACHE_VERSION: u32 = 3;
+
+/// What to walk and how
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ScanKey {
+    pub root: PathBuf,
+    pub max_depth: Option<usize>,
+    pub include_bare: bool,
+    pub prune: Vec<String>,
+    pub follow_symlinks: bool,
+    pub one_file_system: bool,
+}
+
+/// Repos found under a root, in no particular order
+pub fn scan(key: &ScanKey, cache_dir: Option<&Path>) -> Result<Vec<PathBuf>> {
+    let Some(cache_dir) = cache_dir else {
+        return Ok(walk(key)?.repo_paths());
+    };
+    let cache_path = cache_dir.join(cache_file_name(key));
+    if let Some(repos) = read_cache(&cache_path, key) {
+        return Ok(repos);
+    }
+    let walked = walk(key)?;
+    // The cache is only an optimization; failing to write it is not an error
+    let _ = write_cache(&cache_path, key, &walked);
+    Ok(walked.repo_paths())
+}
+
+/// Patterns from one `.git-all-ignore` file, plus the files of enclosing directories
+struct IgnoreScope {
+    base: PathBuf,
+    globs: PathGlobs,
+    parent: Option<Arc<IgnoreScope>>,
+}
+
+impl IgnoreScope {
+    fn ignores(scope: &Option<Arc<IgnoreScope>>, path: &Path) -> bool {
+        let mut scope = scope.as_deref();
+        while let Some(current) = scope {
+            if path
+                .strip_prefix(&current.base)
+                .is_ok_and(|relative| current.globs.matches(relative))
+            {
+                return true;
+            }
+            scope = current.parent.as_deref();
+        }
+        false
+    }
+}
+
+/// A directory the walk passed through, for symlink loop detection
+struct Ancestor {
+    id: DirId,
+    parent: Option<Arc<Ancestor>>,
+}
+
+impl Ancestor {
+    fn contains(ancestor: &Option<Arc<Ancestor>>, id: &DirId) -> bool {
+        let mut ancestor = ancestor.as_deref();
+        while let Some(current) = ancestor {
+            if current.id == *id {
+                return true;
+            }
+            ancestor = current.parent.as_deref();
+        }
+        false
+    }
+}
+
+struct Job {
+    dir: PathBuf,
+    depth: usize,
+    ignores: Option<Arc<IgnoreScope>>,
+    /// Whether a symlink was followed to get here
+    via_symlink: bool,
+    /// Only tracked when following symlinks
+    ancestors: Option<Arc<Ancestor>>,
+}
+
+#[derive(Default)]
+struct Queue {
+    jobs: Vec<Job>,
+    /// Jobs taken by a walker and not yet finished
+    active: usize,
+    error: Option<Error>,
+}
+
+/// A repo, and whether the walk reached it through a symlink
+#[derive(Debug)]
+struct Found {
+    path: PathBuf,
+    via_symlink: bool,
+}
+
+/// Everything one walk saw
+#[derive(Debug, Default)]
+struct Walked {
+    repos: Vec<Found>,
+    /// Scanned directories and ignore files, with their mtimes when read
+    watched: Vec<(PathBuf, Mtime)>,
+}
+
+impl Walked {
+    fn repo_paths(&self) -> Vec<PathBuf> {
+        self.repos.iter().map(|found| found.path.clone()).collect()
+    }
+}
+
+/// Shared state for one walk
+struct Walk<'a> {
+    key: &'a ScanKey,
+    /// The root's device, when the walk stays on one file system
+    root_device: Option<u64>,
+    queue: Mutex<Queue>,
+    wakeup: Condvar,
+}
+
+fn walk(key: &ScanKey) -> Result<Walked> {
+    let ancestors = if key.follow_symlinks {
+        dir_id(&key.root).map(|id| Arc::new(Ancestor { id, parent: None }))
+    } else {
+        None
+    };
+    let walk = Walk {
+        key,
+        root_device: key.one_file_system.then(|| device(&key.root)).flatten(),
+        queue: Mutex::new(Queue {
+            jobs: vec![Job {
+                dir: key.root.clone(),
+                depth: 0,
+                ignores: None,
+                via_symlink: false,
+                ancestors,
+            }],
+            ..Queue::default()
+        }),
+        wakeup: Condvar::new(),
+    };
+    let walkers = thread::available_parallelism().map_or(4, |n| n.get().min(MAX_WALKERS));
+
+    let results: Vec<Walked> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..walkers)
+            .map(|_| scope.spawn(|| walk.run_walker()))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("walker thread panicked"))
+            .collect()
+    });
+
+    if let Some(error) = walk.queue.into_inner().expect("walk queue poisoned").error {
+        return Err(error);
+    }
+    let mut walked = Walked::default();
+    for result in results {
+        walked.repos.extend(result.repos);
+        walked.watched.extend(result.watched);
+    }
+    if key.follow_symlinks {
+        dedupe_by_target(&mut walked.repos);
+    }
+    Ok(walked)
+}
+
+/// Keep one entry per real repository, preferring a path that didn't go
+/// through a symlink, then the first by name
+fn dedupe_by_target(repos: &mut Vec<Found>) {
+    let mut keyed: Vec<(PathBuf, Found)> = repos
+        .drain(..)
+        .map(|found| {
+            let target = found
+                .path
+                .canonicalize()
+                .unwrap_or_else(|_| found.path.clone());
+            (target, found)
+        })
+        .collect();
+    keyed.sort_by(|(a_target, a), (b_target, b)| {
+        (a_target, a.via_symlink, &a.path).cmp(&(b_target, b.via_symlink, &b.path))
+    });
+    keyed.dedup_by(|(a_target, _), (b_target, _)| a_target == b_target);
+    repos.extend(keyed.into_iter().map(|(_, found)| found));
+}
+
+impl Walk<'_> {
+    fn run_walker(&self) -> Walked {
+        let mut walked = Walked::default();
+        loop {
+            let job = {
+                let mut state = self.queue.lock().expect("walk queue poisoned");
+                loop {
+                    if state.error.is_some() {
+                        return walked;
+                    }
+                    if let Some(job) = state.jobs.pop() {
+                        state.active += 1;
+                        break job;
+                    }
+                    if state.active == 0 {
+                        return walked;
+                    }
+                    state = self.wakeup.wait(state).expect("walk queue poisoned");
+                }
+            };
+
+            let result = self.scan_dir(job, &mut walked);
+            let mut state = self.queue.lock().expect("walk queue poisoned");
+            state.active -= 1;
+            match result {
+                Ok(children) => state.jobs.extend(children),
+                Err(e) => {
+                    state.error.get_or_insert(e);
+                }
+            }
+            self.wakeup.notify_all();
+        }
+    }
+
+    /// Read one directory, recording repos and returning subdirectories to walk
+    fn scan_dir(&self, job: Job, walked: &mut Walked) -> Result<Vec<Job>> {
+        let key = self.key;
+        // Taken before listing, so a change made mid-walk invalidates the cache
+        walked.watched.push((job.dir.clone(), Mtime::of(&job.dir)));
+        let mut ignores = job.ignores;
+        if let Some(globs) = PathGlobs::load_ignore_file(&job.dir)? {
+            let ignore_file = job.dir.join(IGNORE_FILE);
+            walked
+                .watched
+                .push((ignore_file.clone(), Mtime::of(&ignore_file)));
+            ignores = Some(Arc::new(IgnoreScope {
+                base: job.dir.clone(),
+                globs,
+                parent: ignores,
+            }));
+        }
+
+        let next_depth = job.depth + 1;
+        let should_descend = key.max_depth.is_none_or(|max| next_depth < max);
+        let mut children = Vec::new();
+        for entry in fs::read_dir(&job.dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let path = entry.path();
+            let is_symlink = file_type.is_symlink();
+            let is_dir = file_type.is_dir() || (is_symlink && key.follow_symlinks && path.is_dir());
+            if !is_dir {
+                continue;
+            }
+            if IgnoreScope::ignores(&ignores, &path) {
+                continue;
+            }
+            if self
+                .root_device
+                .is_some_and(|root| device(&path) != Some(root))
+            {
+                continue;
+            }
+
+            let via_symlink = job.via_symlink || is_symlink;
+            // Taken before the `.git` check, like a scanned directory's mtime
+            let mtime = Mtime::of(&path);
+            if path.join(".git").exists() {
+                walked.repos.push(Found { path, via_symlink });
+                continue;
+            }
+            let name = entry.file_name();
+            // A directory checked but not walked is still watched, so a later
+            // `git init` in it invalidates the cache
+            if key.prune.iter().any(|pruned| name == pruned.as_str()) {
+                walked.watched.push((path, mtime));
+                continue;
+            }
+            // Never descend into a bare repo's objects and refs
+            if is_bare_repo(&path) {
+                if key.include_bare {
+                    walked.repos.push(Found { path, via_symlink });
+                } else {
+                    walked.watched.push((path, mtime));
+                }
+                continue;
+            }
+            if !should_descend {
+                walked.watched.push((path, mtime));
+                continue;
+            }
+
+            let ancestors = if key.follow_symlinks {
+                let Some(id) = dir_id(&path) else {
+                    continue;
+                };
+                // A symlink back to a directory above us would recurse forever
+                if Ancestor::contains(&job.ancestors, &id) {
+                    continue;
+                }
+                Some(Arc::new(Ancestor {
+                    id,
+                    parent: job.ancestors.clone(),
+                }))
+            } else {
+                None
+            };
+            children.push(Job {
+                dir: path,
+                depth: next_depth,
+                ignores: ignores.clone(),
+                via_symlink,
+                ancestors,
+            });
+        }
+        Ok(children)
+    }
+}
+
+/// Identifies a directory however it was reached
+#[cfg(unix)]
+type DirId = (u64, u64);
+#[cfg(not(unix))]
+type DirId = PathBuf;
+
+#[cfg(unix)]
+fn dir_id(path: &Path) -> Option<DirId> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn dir_id(path: &Path) -> Option<DirId> {
+    path.canonicalize().ok()
+}
+
+/// The file system a path lives on; `--one-file-system` is a no-op where
+/// this isn't available
+#[cfg(unix)]
+fn device(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|metadata| metadata.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// A modification time as stored in the cache; `None` when it can't be read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Mtime(Option<(u64, u32)>);
+
+impl Mtime {
+    fn of(path: &Path) -> Self {
+        let since_epoch = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok());
+        Mtime(since_epoch.map(|time| (time.as_secs(), time.subsec_nanos())))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    key: ScanKey,
+    watched: Vec<(PathBuf, Mtime)>,
+    repos: Vec<PathBuf>,
+}
+
+fn cache_file_name(key: &ScanKey) -> String {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    format!("discovery-{:016x}.json", hasher.finish())
+}
+
+/// Cached repos for `key`, if nothing the walk looked at has changed since
+fn read_cache(path: &Path, key: &ScanKey) -> Option<Vec<PathBuf>> {
+    let contents = fs::read_to_string(path).ok()?;
+    let cache: CacheFile = serde_json::from_str(&contents).ok()?;
+    if cache.version != CACHE_VERSION || &cache.key != key {
+        return None;
+    }
+    let unchanged = cache
+        .watched
+        .iter()
+        .all(|(path, mtime)| mtime.0.is_some() && Mtime::of(path) == *mtime);
+    // A repo's own mtime isn't watched, so check it is still a repo
+    let repos_exist = cache
+        .repos
+        .iter()
+        .all(|repo| repo.join(".git").exists() || is_bare_repo(repo));
+    (unchanged && repos_exist).then_some(cache.repos)
+}
+
+fn write_cache(path: &Path, key: &ScanKey, walked: &Walked) -> Result<()> {
+    let cache = CacheFile {
+        version: CACHE_VERSION,
+        key: key.clone(),
+        watched: walked.watched.clone(),
+        repos: walked.repo_paths(),
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Write then rename so concurrent runs never read a partial file
+    let partial = path.with_extension(format!("json.{}", std::process::id()));
+    fs::write(&partial, serde_json::to_vec(&cache)?)?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(root: &Path) -> ScanKey {
+        ScanKey {
+            root: root.to_path_buf(),
+            max_depth: None,
+            include_bare: false,
+            prune: DEFAULT_PRUNE.iter().map(|name| name.to_string()).collect(),
+            follow_symlinks: false,
+            one_file_system: false,
+        }
+    }
+
+    fn sorted(mut repos: Vec<PathBuf>) -> Vec<PathBuf> {
+        repos.sort();
+        repos
+    }
+
+    #[test]
+    fn test_walk_prunes_heavy_directories() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir_all(root.join("web/.git")).unwrap();
+        fs::create_dir_all(root.join("web/node_modules/left-pad/.git")).unwrap();
+        fs::create_dir_all(root.join("tools/node_modules/dep/.git")).unwrap();
+        fs::create_dir_all(root.join("tools/cli/.git")).unwrap();
+        // A repo that happens to have a pruned name is still found
+        fs::create_dir_all(root.join("target/.git")).unwrap();
+
+        assert_eq!(
+            sorted(scan(&key(root), None).unwrap()),
+            vec![
+                root.join("target"),
+                root.join("tools/cli"),
+                root.join("web")
+            ]
+        );
+
+        let unpruned = ScanKey {
+            prune: Vec::new(),
+            ..key(root)
+        };
+        assert!(
+            scan(&unpruned, None)
+                .unwrap()
+                .contains(&root.join("tools/node_modules/dep"))
+        );
+    }
+
+    #[test]
+    fn test_walk_reports_unreadable_root() {
+        let temp = tempfile::tempdir().unwrap();
+        assert!(scan(&key(&temp.path().join("missing")), None).is_err());
+    }
+
+    #[test]
+    fn test_cache_is_reused_until_a_directory_changes() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("src");
+        let cache_dir = temp.path().join("cache");
+        fs::create_dir_all(root.join("team/api/.git")).unwrap();
+        let key = key(&root);
+
+        assert_eq!(
+            scan(&key, Some(&cache_dir)).unwrap(),
+            vec![root.join("team/api")]
+        );
+        let cache_path = cache_dir.join(cache_file_name(&key));
+        assert_eq!(
+            read_cache(&cache_path, &key),
+            Some(vec![root.join("team/api")])
+        );
+
+        // Creating a repo touches its parent directory's mtime
+        fs::create_dir_all(root.join("team/web/.git")).unwrap();
+        assert_eq!(read_cache(&cache_path, &key), None);
+        assert_eq!(
+            sorted(scan(&key, Some(&cache_dir)).unwrap()),
+            vec![root.join("team/api"), root.join("team/web")]
+        );
+
+        // Removing `.git` leaves the parent's mtime alone but drops the repo
+        fs::remove_dir(root.join("team/web/.git")).unwrap();
+        assert_eq!(read_cache(&cache_path, &key), None);
+
+        let deeper = ScanKey {
+            max_depth: Some(1),
+            ..key.clone()
+        };
+        assert_ne!(cache_file_name(&deeper), cache_file_name(&key));
+    }
+
+    #[test]
+    fn test_cache_notices_git_init_in_unwalked_directories() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("src");
+        let cache_dir = temp.path().join("cache");
+        fs::create_dir_all(root.join("a/.git")).unwrap();
+        fs::create_dir_all(root.join("b")).unwrap();
+        fs::create_dir_all(root.join("target")).unwrap();
+        // The default depth of 1 checks `b` for `.git` but never lists it
+        let key = ScanKey {
+            max_depth: Some(1),
+            ..key(&root)
+        };
+        let cache_path = cache_dir.join(cache_file_name(&key));
+
+        assert_eq!(scan(&key, Some(&cache_dir)).unwrap(), vec![root.join("a")]);
+        let status = std::process::Command::new("git")
+            .args(["init", "-q"])
+            .arg(root.join("b"))
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(read_cache(&cache_path, &key), None);
+        assert_eq!(
+            sorted(scan(&key, Some(&cache_dir)).unwrap()),
+            vec![root.join("a"), root.join("b")]
+        );
+
+        // Same for a directory skipped by name
+        fs::create_dir(root.join("target/.git")).unwrap();
+        assert_eq!(read_cache(&cache_path, &key), None);
+    }
+}
@@ -0,0 +1,325 @@
+//! Selecting repositories by their current state (branch, remotes, worktree)
+//! between discovery and running the main command.
+//!
+//! Branch and remote checks read `.git` files directly. Worktree checks run
+//! one `git status` per repo, in parallel, and only when a status predicate is
+//! set.
+
+use globset::{Glob, GlobMatcher};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::commands::status::StatusSummary;
+use crate::repo::{DETACHED_HEAD, current_branch, remote_host, remotes};
+
+/// Parse a `--on-branch` / `--not-on-branch` glob such as `main` or `release/*`
+pub fn parse_branch_glob(value: &str) -> Result<Glob, String> {
+    Glob::new(value.trim()).map_err(|e| e.to_string())
+}
+
+/// State predicates a repo must satisfy to be included in a run
+#[derive(Debug, Default)]
+pub struct Selection {
+    pub only_dirty: bool,
+    pub only_clean: bool,
+    pub only_behind: bool,
+    pub only_ahead: bool,
+    pub on_branch: Option<GlobMatcher>,
+    pub not_on_branch: Option<GlobMatcher>,
+    pub has_remote: Option<String>,
+    pub remote_host: Option<String>,
+}
+
+/// A repo left out of the run, and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Excluded {
+    pub repo: PathBuf,
+    pub reason: String,
+}
+
+impl Selection {
+    pub fn is_active(&self) -> bool {
+        self.needs_status()
+            || self.on_branch.is_some()
+            || self.not_on_branch.is_some()
+            || self.has_remote.is_some()
+            || self.remote_host.is_some()
+    }
+
+    fn needs_status(&self) -> bool {
+        self.only_dirty || self.only_clean || self.only_behind || self.only_ahead
+    }
+
+    /// Split `repos` into those to run and those excluded, preserving order.
+    /// Repos are checked on up to `workers` threads (0 = one per repo).
+    pub fn apply(&self, repos: Vec<PathBuf>, workers: usize) -> (Vec<PathBuf>, Vec<Excluded>) {
+        if !self.is_active() || repos.is_empty() {
+            return (repos, Vec::new());
+        }
+
+        let reasons = evaluate_parallel(&repos, workers, |repo| self.exclusion_reason(repo));
+
+        let mut selected = Vec::new();
+        let mut excluded = Vec::new();
+        for (repo, reason) in repos.into_iter().zip(reasons) {
+            match reason {
+                Some(reason) => excluded.push(Excluded { repo, reason }),
+                None => selected.push(repo),
+            }
+        }
+        (selected, excluded)
+    }
+
+    /// Why `repo` fails the selection, or `None` when it should run.
+    /// Cheap file-based checks come before running `git status`.
+    fn exclusion_reason(&self, repo: &Path) -> Option<String> {
+        if let Some(reason) = self.branch_exclusion(repo) {
+            return Some(reason);
+        }
+        if let Some(reason) = self.remote_exclusion(repo) {
+            return Some(reason);
+        }
+        if !self.needs_status() {
+            return None;
+        }
+
+        let summary = match read_status(repo) {
+            Ok(summary) => summary,
+            Err(error) => return Some(format!("status failed: {error}")),
+        };
+        if self.only_dirty && !summary.is_dirty() {
+            return Some("clean".to_string());
+        }
+        if self.only_clean && summary.is_dirty() {
+            return Some(format!("dirty ({})", summary.message()));
+        }
+        if self.only_behind && summary.behind == 0 {
+            return Some("not behind upstream".to_string());
+        }
+        if self.only_ahead && summary.upstream.is_none() {
+            return Some("no upstream".to_string());
+        }
+        if self.only_ahead && summary.ahead == 0 {
+            return Some("nothing to push".to_string());
+        }
+        None
+    }
+
+    fn branch_exclusion(&self, repo: &Path) -> Option<String> {
+        if self.on_branch.is_none() && self.not_on_branch.is_none() {
+            return None;
+        }
+
+        let branch = current_branch(repo).unwrap_or_else(|| "unknown".to_string());
+        if let Some(on_branch) = &self.on_branch
+            && (branch == DETACHED_HEAD || !on_branch.is_match(&branch))
+        {
+            return Some(format!(
+                "on {}, not {}",
+                describe_branch(&branch),
+                on_branch.glob()
+            ));
+        }
+        if let Some(not_on_branch) = &self.not_on_branch
+            && branch != DETACHED_HEAD
+            && not_on_branch.is_match(&branch)
+        {
+            return Some(format!("on {}", describe_branch(&branch)));
+        }
+        None
+    }
+
+    fn remote_exclusion(&self, repo: &Path) -> Option<String> {
+        if self.has_remote.is_none() && self.remote_host.is_none() {
+            return None;
+        }
+
+        let remotes = remotes(repo);
+        if let Some(name) = &self.has_remote
+            && !remotes.iter().any(|remote| &remote.name == name)
+        {
+            return Some(format!("no remote named {name}"));
+        }
+        if let Some(host) = &self.remote_host
+            && !remotes.iter().any(|remote| {
+                remote_host(&remote.url).is_some_and(|h| h.eq_ignore_ascii_case(host))
+            })
+        {
+            return Some(format!("no remote on {host}"));
+        }
+        None
+    }
+}
+
+fn describe_branch(branch: &str) -> String {
+    if branch == DETACHED_HEAD {
+        "detached HEAD".to_string()
+    } else {
+        format!("branch {branch}")
+    }
+}
+
+/// Run `git status --porcelain -b` without taking optional index locks
+pub fn read_status(repo: &Path) -> Result<StatusSummary, String> {
+    let output = Command::new("git")
+        .args(["--no-optional-locks", "-C"])
+        .arg(repo)
+        .args(["status", "--porcelain", "-b"])
+        .stdin(Stdio::null())
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(stderr.lines().next().unwrap_or("unknown error").to_string());
+    }
+    Ok(StatusSummary::parse(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+/// Evaluate `check` for every repo on a small pool of threads, keeping input order
+fn evaluate_parallel<T, F>(repos: &[PathBuf], workers: usize, check: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&Path) -> T + Sync,
+{
+    let threads = if workers == 0 {
+        repos.len()
+    } else {
+        workers.min(repos.len())
+    };
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<(usize, T)>> = Mutex::new(Vec::with_capacity(repos.len()));
+
+    std::thread::scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| {
+                loop {
+                    let idx = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(repo) = repos.get(idx) else {
+                        break;
+                    };
+                    let result = check(repo);
+                    results.lock().unwrap().push((idx, result));
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(idx, _)| *idx);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn matcher(pattern: &str) -> GlobMatcher {
+        parse_branch_glob(pattern).unwrap().compile_matcher()
+    }
+
+    fn create_repo(path: &Path, head: &str, config: &str) {
+        fs::create_dir_all(path.join(".git")).unwrap();
+        fs::write(path.join(".git/HEAD"), head).unwrap();
+        fs::write(path.join(".git/config"), config).unwrap();
+    }
+
+    #[test]
+    fn test_branch_predicates() {
+        let temp = tempfile::tempdir().unwrap();
+        let main = temp.path().join("main");
+        let feature = temp.path().join("feature");
+        let detached = temp.path().join("detached");
+        create_repo(&main, "ref: refs/heads/main\n", "");
+        create_repo(&feature, "ref: refs/heads/feat/login\n", "");
+        create_repo(&detached, "3f1c2a9e\n", "");
+        let repos = vec![main.clone(), feature.clone(), detached.clone()];
+
+        let selection = Selection {
+            on_branch: Some(matcher("main")),
+            ..Selection::default()
+        };
+        let (selected, excluded) = selection.apply(repos.clone(), 2);
+        assert_eq!(selected, vec![main.clone()]);
+        assert_eq!(
+            excluded,
+            vec![
+                Excluded {
+                    repo: feature.clone(),
+                    reason: "on branch feat/login, not main".to_string(),
+                },
+                Excluded {
+                    repo: detached.clone(),
+                    reason: "on detached HEAD, not main".to_string(),
+                },
+            ]
+        );
+
+        let selection = Selection {
+            not_on_branch: Some(matcher("main")),
+            ..Selection::default()
+        };
+        let (selected, excluded) = selection.apply(repos, 0);
+        assert_eq!(selected, vec![feature, detached]);
+        assert_eq!(excluded[0].reason, "on branch main");
+    }
+
+    #[test]
+    fn test_remote_predicates() {
+        let temp = tempfile::tempdir().unwrap();
+        let github = temp.path().join("github");
+        let internal = temp.path().join("internal");
+        let local = temp.path().join("local");
+        create_repo(
+            &github,
+            "ref: refs/heads/main\n",
+            "[remote \"origin\"]\n\turl = git@github.com:org/a.git\n\
+             [remote \"upstream\"]\n\turl = https://github.com/up/a.git\n",
+        );
+        create_repo(
+            &internal,
+            "ref: refs/heads/main\n",
+            "[remote \"origin\"]\n\turl = ssh://git@gitlab.internal:2222/org/b.git\n",
+        );
+        create_repo(&local, "ref: refs/heads/main\n", "");
+        let repos = vec![github.clone(), internal.clone(), local.clone()];
+
+        let selection = Selection {
+            has_remote: Some("upstream".to_string()),
+            ..Selection::default()
+        };
+        let (selected, excluded) = selection.apply(repos.clone(), 1);
+        assert_eq!(selected, vec![github.clone()]);
+        assert_eq!(excluded[0].reason, "no remote named upstream");
+
+        let selection = Selection {
+            remote_host: Some("GitLab.internal".to_string()),
+            ..Selection::default()
+        };
+        let (selected, excluded) = selection.apply(repos, 1);
+        assert_eq!(selected, vec![internal]);
+        assert_eq!(excluded[1].reason, "no remote on GitLab.internal");
+    }
+
+    #[test]
+    fn test_inactive_selection_keeps_everything() {
+        let repos = vec![PathBuf::from("/does/not/exist")];
+        let (selected, excluded) = Selection::default().apply(repos.clone(), 4);
+        assert_eq!(selected, repos);
+        assert!(excluded.is_empty());
+    }
+
+    #[test]
+    fn test_evaluate_parallel_preserves_order() {
+        let repos: Vec<PathBuf> = (0..20).map(|i| PathBuf::from(i.to_string())).collect();
+        let results = evaluate_parallel(&repos, 3, |repo| repo.to_string_lossy().into_owned());
+        let expected: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        assert_eq!(results, expected);
+    }
+}
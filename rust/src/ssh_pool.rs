@@ -0,0 +1,338 @@
+//! Pre-warmed SSH ControlMaster pool (`--ssh-masters`).
+//!
+//! Plain `--ssh-multiplexing` funnels every repo on a host through one master
+//! that is opened by whichever git process gets there first, so runs hit the
+//! server's `MaxSessions` cap and race on cold start (SPEC 6.5.1). The pool
+//! opens up to K masters per host before the fan-out, spreads that host's
+//! repos across them round-robin, and caps how many sessions each carries.
+//! Each git process is pointed at its master with a per-process
+//! `core.sshCommand`, and the masters are closed when the run ends.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+#[cfg(unix)]
+use std::os::unix::fs::DirBuilderExt;
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+
+use crate::cancel;
+use crate::url_rewrite::SshEndpoint;
+
+/// How long masters get to authenticate before their repos connect directly
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often startup checks for sockets
+const STARTUP_POLL: Duration = Duration::from_millis(50);
+
+/// Size of the pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolSize {
+    /// Masters opened per SSH endpoint
+    pub masters: usize,
+    /// Git sessions each master carries at once
+    pub sessions: usize,
+}
+
+struct Master {
+    endpoint: SshEndpoint,
+    control_path: PathBuf,
+    child: Option<Child>,
+}
+
+/// Masters planned (and, once started, running) for one fan-out
+pub struct SshPool {
+    dir: PathBuf,
+    masters: Vec<Master>,
+    /// Master index per repo; `None` for repos that don't connect over SSH
+    assignments: Vec<Option<usize>>,
+    sessions: usize,
+}
+
+impl SshPool {
+    /// Assign each repo with an SSH endpoint to one of its endpoint's masters,
+    /// round-robin in repo order. Returns `None` when no repo uses SSH.
+    pub fn plan(endpoints: &[Option<SshEndpoint>], size: PoolSize) -> Option<SshPool> {
+        let dir = std::env::temp_dir().join(format!("git-all-ssh-{}", std::process::id()));
+
+        let mut repos_per_endpoint: Vec<(SshEndpoint, Vec<usize>)> = Vec::new();
+        let mut positions: HashMap<&SshEndpoint, usize> = HashMap::new();
+        for (idx, endpoint) in endpoints.iter().enumerate() {
+            let Some(endpoint) = endpoint else { continue };
+            let position = *positions.entry(endpoint).or_insert_with(|| {
+                repos_per_endpoint.push((endpoint.clone(), Vec::new()));
+                repos_per_endpoint.len() - 1
+            });
+            repos_per_endpoint[position].1.push(idx);
+        }
+        if repos_per_endpoint.is_empty() {
+            return None;
+        }
+
+        let mut masters = Vec::new();
+        let mut assignments = vec![None; endpoints.len()];
+        for (host_no, (endpoint, repos)) in repos_per_endpoint.into_iter().enumerate() {
+            let first = masters.len();
+            let count = size.masters.clamp(1, repos.len());
+            for master_no in 0..count {
+                masters.push(Master {
+                    endpoint: endpoint.clone(),
+                    control_path: dir.join(format!("{host_no}-{master_no}")),
+                    child: None,
+                });
+            }
+            for (nth, idx) in repos.into_iter().enumerate() {
+                assignments[idx] = Some(first + nth % count);
+            }
+        }
+
+        Some(SshPool {
+            dir,
+            masters,
+            assignments,
+            sessions: size.sessions.max(1),
+        })
+    }
+
+    /// Number of masters in the pool
+    pub fn len(&self) -> usize {
+        self.masters.len()
+    }
+
+    /// Git sessions each master carries at once
+    pub fn sessions(&self) -> usize {
+        self.sessions
+    }
+
+    /// The master repo `idx` is assigned to
+    pub fn master_of(&self, idx: usize) -> Option<usize> {
+        self.assignments.get(idx).copied().flatten()
+    }
+
+    /// Socket git should reach repo `idx`'s master through
+    pub fn control_path(&self, idx: usize) -> Option<&Path> {
+        self.master_of(idx)
+            .map(|master| self.masters[master].control_path.as_path())
+    }
+
+    /// The `ssh` commands that open each master, for dry-run output
+    pub fn master_commands(&self) -> Vec<String> {
+        self.masters
+            .iter()
+            .map(|master| {
+                let mut argv = vec!["ssh".to_string()];
+                argv.extend(master_args(master));
+                argv.join(" ")
+            })
+            .collect()
+    }
+
+    /// Open every master and wait for its socket. Repos whose master doesn't
+    /// come up are unassigned, so they connect directly instead.
+    pub fn start(&mut self) {
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        builder.mode(0o700);
+        if let Err(err) = builder.create(&self.dir) {
+            eprintln!(
+                "git-all: can't create {} ({err}); not pre-warming ssh masters",
+                self.dir.display()
+            );
+            self.assignments.fill(None);
+            return;
+        }
+
+        for master in &mut self.masters {
+            let mut cmd = Command::new("ssh");
+            cmd.args(master_args(master))
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null());
+            // Own process group, like git children, so the terminal's Ctrl-C
+            // doesn't drop masters out from under running fetches
+            #[cfg(unix)]
+            cmd.process_group(0);
+            master.child = cmd.spawn().ok();
+        }
+
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        let mut pending: Vec<usize> = (0..self.masters.len()).collect();
+        while !pending.is_empty() && Instant::now() < deadline && !cancel::requested() {
+            pending.retain(|&idx| {
+                let master = &mut self.masters[idx];
+                let exited = match &mut master.child {
+                    Some(child) => !matches!(child.try_wait(), Ok(None)),
+                    None => true,
+                };
+                !exited && !master.control_path.exists()
+            });
+            if !pending.is_empty() {
+                std::thread::sleep(STARTUP_POLL);
+            }
+        }
+
+        for (idx, master) in self.masters.iter_mut().enumerate() {
+            if master.control_path.exists() {
+                continue;
+            }
+            eprintln!(
+                "git-all: ssh master for {} didn't start; its repos connect directly",
+                master.endpoint
+            );
+            stop(master);
+            for assignment in &mut self.assignments {
+                if *assignment == Some(idx) {
+                    *assignment = None;
+                }
+            }
+        }
+    }
+}
+
+impl Drop for SshPool {
+    fn drop(&mut self) {
+        for master in &mut self.masters {
+            stop(master);
+        }
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Arguments after `ssh` that open `master` in the foreground
+fn master_args(master: &Master) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "-o",
+        "ControlMaster=yes",
+        "-o",
+        &format!("ControlPath={}", master.control_path.display()),
+        "-o",
+        "ControlPersist=no",
+        "-o",
+        "BatchMode=yes",
+        "-N",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+    if let Some(port) = master.endpoint.port {
+        args.extend(["-p".to_string(), port.to_string()]);
+    }
+    if let Some(user) = &master.endpoint.user {
+        args.extend(["-l".to_string(), user.clone()]);
+    }
+    args.push(master.endpoint.host.clone());
+    args
+}
+
+/// Close a master; ssh removes its socket on SIGTERM
+fn stop(master: &mut Master) {
+    let Some(mut child) = master.child.take() else {
+        return;
+    };
+    #[cfg(unix)]
+    // SAFETY: kill(2) has no memory-safety preconditions
+    unsafe {
+        libc::kill(child.id() as libc::pid_t, libc::SIGTERM);
+    }
+    #[cfg(not(unix))]
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// `core.sshCommand` that reaches a master through `control_path`. The path is
+/// quoted because git runs the command through the shell.
+pub fn ssh_command(control_path: &Path) -> String {
+    let path = control_path.to_string_lossy();
+    let quoted = if path
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "/._-".contains(c))
+    {
+        path.into_owned()
+    } else {
+        format!("'{}'", path.replace('\'', r"'\''"))
+    };
+    format!("ssh -o ControlMaster=no -o ControlPath={quoted}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint(host: &str) -> Option<SshEndpoint> {
+        Some(SshEndpoint {
+            user: Some("git".to_string()),
+            host: host.to_string(),
+            port: None,
+        })
+    }
+
+    #[test]
+    fn test_plan_round_robin_per_endpoint() {
+        let endpoints = [
+            endpoint("github.com"),
+            endpoint("github.com"),
+            None,
+            endpoint("gitlab.internal"),
+            endpoint("github.com"),
+        ];
+        let size = PoolSize {
+            masters: 2,
+            sessions: 4,
+        };
+        let pool = SshPool::plan(&endpoints, size).expect("pool");
+
+        // github.com gets two masters, gitlab.internal only needs one
+        assert_eq!(pool.len(), 3);
+        let masters: Vec<Option<usize>> = (0..endpoints.len()).map(|i| pool.master_of(i)).collect();
+        assert_eq!(masters, [Some(0), Some(1), None, Some(2), Some(0)]);
+        assert!(pool.control_path(0).unwrap().ends_with("0-0"));
+        assert!(pool.control_path(3).unwrap().ends_with("1-0"));
+        assert_eq!(pool.sessions(), 4);
+    }
+
+    #[test]
+    fn test_plan_without_ssh_repos() {
+        let size = PoolSize {
+            masters: 2,
+            sessions: 4,
+        };
+        assert!(SshPool::plan(&[None, None], size).is_none());
+    }
+
+    #[test]
+    fn test_master_commands() {
+        let endpoints = [Some(SshEndpoint {
+            user: None,
+            host: "host.lan".to_string(),
+            port: Some(2222),
+        })];
+        let size = PoolSize {
+            masters: 4,
+            sessions: 8,
+        };
+        let pool = SshPool::plan(&endpoints, size).expect("pool");
+        let commands = pool.master_commands();
+        assert_eq!(commands.len(), 1);
+        assert!(commands[0].starts_with("ssh -o ControlMaster=yes -o ControlPath="));
+        assert!(
+            commands[0].ends_with("-N -p 2222 host.lan"),
+            "{}",
+            commands[0]
+        );
+    }
+
+    #[test]
+    fn test_ssh_command_quotes_paths() {
+        assert_eq!(
+            ssh_command(Path::new("/tmp/git-all-ssh-1/0-0")),
+            "ssh -o ControlMaster=no -o ControlPath=/tmp/git-all-ssh-1/0-0"
+        );
+        assert_eq!(
+            ssh_command(Path::new("/tmp/my dir/0-0")),
+            "ssh -o ControlMaster=no -o ControlPath='/tmp/my dir/0-0'"
+        );
+    }
+}
@@ -16,6 +16,8 @@ pub struct RepoTraceSample {
     pub stdout_bytes: usize,
     pub stderr_bytes: usize,
     pub success: bool,
+    pub timed_out: bool,
+    pub attempts: u32,
 }
 
 impl RepoTraceSample {
@@ -78,7 +80,8 @@ impl TraceSink {
                 "git-all-trace phase=repo idx={idx} repo={repo_name:?} ",
                 "start_ms={start_ms} spawn_ms={spawn_ms} exit_ms={exit_ms} ",
                 "printed_ms={printed_ms} run_ms={run_ms} ordered_wait_ms={ordered_wait_ms} ",
-                "stdout_bytes={stdout_bytes} stderr_bytes={stderr_bytes} success={success}"
+                "stdout_bytes={stdout_bytes} stderr_bytes={stderr_bytes} success={success} ",
+                "timed_out={timed_out} attempts={attempts}"
             ),
             idx = idx,
             repo_name = repo_name,
@@ -91,6 +94,8 @@ impl TraceSink {
             stdout_bytes = sample.stdout_bytes,
             stderr_bytes = sample.stderr_bytes,
             success = sample.success,
+            timed_out = sample.timed_out,
+            attempts = sample.attempts,
         ))
     }
 
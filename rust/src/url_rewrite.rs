@@ -0,0 +1,462 @@
+//! `insteadOf` rules behind `--ssh` / `--https`.
+//!
+//! Rules are generated per remote host, so self-hosted GitLab, Gitea or
+//! Bitbucket remotes are rewritten as well as GitHub ones. Hosts come from
+//! each repo's remotes; configured `--url-host` entries add hosts and say
+//! which SSH user and port a host uses.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::repo::remotes;
+use crate::runner::UrlScheme;
+
+/// Rewritten when nothing else is known, e.g. a repo without remotes
+const FALLBACK_HOST: &str = "github.com";
+const DEFAULT_SSH_USER: &str = "git";
+
+/// How one host is reached over SSH and HTTPS
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RewriteHost {
+    pub host: String,
+    pub ssh_user: String,
+    /// Set when SSH is not on port 22, which needs `ssh://` URLs
+    pub ssh_port: Option<u16>,
+    /// Set when HTTPS is not on port 443
+    pub https_port: Option<u16>,
+}
+
+impl RewriteHost {
+    fn new(host: &str) -> Self {
+        RewriteHost {
+            host: host.to_lowercase(),
+            ssh_user: DEFAULT_SSH_USER.to_string(),
+            ssh_port: None,
+            https_port: None,
+        }
+    }
+
+    /// `host`, bracketed when it is an IPv6 literal
+    fn authority(&self) -> String {
+        if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        }
+    }
+
+    fn ssh_base(&self) -> String {
+        let (user, host) = (&self.ssh_user, self.authority());
+        match self.ssh_port {
+            Some(port) => format!("ssh://{user}@{host}:{port}/"),
+            None => format!("{user}@{host}:"),
+        }
+    }
+
+    fn https_base(&self) -> String {
+        match self.https_port {
+            Some(port) => format!("https://{}:{port}/", self.authority()),
+            None => format!("https://{}/", self.authority()),
+        }
+    }
+
+    /// `(base, insteadOf)` pairs that send this host's URLs to `scheme`
+    fn rules(&self, scheme: UrlScheme) -> Vec<(String, String)> {
+        let host = self.authority();
+        let sources = match scheme {
+            UrlScheme::Ssh => {
+                let mut sources = vec![format!("https://{host}/")];
+                if let Some(port) = self.https_port {
+                    sources.push(format!("https://{host}:{port}/"));
+                }
+                sources
+            }
+            UrlScheme::Https => {
+                let user = &self.ssh_user;
+                let mut sources = vec![format!("{user}@{host}:"), format!("ssh://{user}@{host}/")];
+                if let Some(port) = self.ssh_port {
+                    sources.push(format!("ssh://{user}@{host}:{port}/"));
+                }
+                sources
+            }
+        };
+        let base = match scheme {
+            UrlScheme::Ssh => self.ssh_base(),
+            UrlScheme::Https => self.https_base(),
+        };
+        sources
+            .into_iter()
+            .filter(|source| *source != base)
+            .map(|source| (base.clone(), source))
+            .collect()
+    }
+}
+
+/// The `[user@]host[:ssh-port]` form `parse_url_host` reads
+impl fmt::Display for RewriteHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.ssh_user, self.authority())?;
+        if let Some(port) = self.ssh_port {
+            write!(f, ":{port}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a `--url-host` entry: `[user@]host[:ssh-port]`
+pub fn parse_url_host(value: &str) -> Result<RewriteHost, String> {
+    let invalid = || {
+        format!(
+            "invalid url host: {value}. Use [USER@]HOST[:SSH-PORT], e.g. git@gitlab.internal:2222."
+        )
+    };
+    let value = value.trim();
+    let (user, host_port) = match value.split_once('@') {
+        Some((user, rest)) if !user.is_empty() => (Some(user), rest),
+        Some(_) => return Err(invalid()),
+        None => (None, value),
+    };
+    let (host, port) = split_port(host_port).ok_or_else(invalid)?;
+    if host.is_empty() || host.contains('/') {
+        return Err(invalid());
+    }
+
+    let mut entry = RewriteHost::new(host);
+    if let Some(user) = user {
+        entry.ssh_user = user.to_string();
+    }
+    entry.ssh_port = port;
+    Ok(entry)
+}
+
+/// Split `host[:port]` or `[v6]:port`; `None` when the port isn't a number
+fn split_port(authority: &str) -> Option<(&str, Option<u16>)> {
+    if let Some(bracketed) = authority.strip_prefix('[') {
+        let (host, rest) = bracketed.split_once(']')?;
+        return match rest.strip_prefix(':') {
+            Some(port) => Some((host, Some(port.parse().ok()?))),
+            None if rest.is_empty() => Some((host, None)),
+            None => None,
+        };
+    }
+    match authority.split_once(':') {
+        Some((host, port)) => Some((host, Some(port.parse().ok()?))),
+        None => Some((authority, None)),
+    }
+}
+
+/// What a remote URL says about its host
+#[derive(Debug, PartialEq, Eq)]
+enum RemoteKind {
+    Ssh { user: Option<String> },
+    Https,
+}
+
+/// Parse a remote URL into its kind, host and explicit port
+fn parse_remote(url: &str) -> Option<(RemoteKind, String, Option<u16>)> {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        // scp-like syntax: [user@]host:path, where the colon comes before any slash
+        let (authority, _) = url.split_once(':')?;
+        if authority.contains('/') || authority.is_empty() {
+            return None;
+        }
+        let (user, host) = match authority.rsplit_once('@') {
+            Some((user, host)) => (Some(user.to_string()), host),
+            None => (None, authority),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        return Some((RemoteKind::Ssh { user }, host.to_lowercase(), None));
+    };
+
+    let authority = rest.split('/').next()?;
+    let (user, host_port) = match authority.rsplit_once('@') {
+        Some((user, host_port)) => (Some(user), host_port),
+        None => (None, authority),
+    };
+    let (host, port) = split_port(host_port)?;
+    if host.is_empty() {
+        return None;
+    }
+    let kind = match scheme.to_ascii_lowercase().as_str() {
+        "ssh" | "git+ssh" | "ssh+git" => RemoteKind::Ssh {
+            user: user.map(|user| user.split(':').next().unwrap_or(user).to_string()),
+        },
+        "https" => RemoteKind::Https,
+        _ => return None,
+    };
+    Some((kind, host.to_lowercase(), port))
+}
+
+/// Hosts of the given remote URLs, merged with `configured` entries, which
+/// win for the SSH user and port. Sorted by host.
+fn merge_hosts<'a>(
+    urls: impl IntoIterator<Item = &'a str>,
+    configured: &[RewriteHost],
+) -> Vec<RewriteHost> {
+    let mut hosts: BTreeMap<String, RewriteHost> = BTreeMap::new();
+    for (kind, host, port) in urls.into_iter().filter_map(parse_remote) {
+        let entry = hosts
+            .entry(host.clone())
+            .or_insert_with(|| RewriteHost::new(&host));
+        match kind {
+            RemoteKind::Ssh { user } => {
+                if let Some(user) = user {
+                    entry.ssh_user = user;
+                }
+                entry.ssh_port = entry.ssh_port.or(port.filter(|&port| port != 22));
+            }
+            RemoteKind::Https => {
+                entry.https_port = entry.https_port.or(port.filter(|&port| port != 443));
+            }
+        }
+    }
+    for configured in configured {
+        let entry = hosts
+            .entry(configured.host.clone())
+            .or_insert_with(|| RewriteHost::new(&configured.host));
+        entry.ssh_user = configured.ssh_user.clone();
+        entry.ssh_port = configured.ssh_port;
+    }
+    if hosts.is_empty() {
+        hosts.insert(FALLBACK_HOST.to_string(), RewriteHost::new(FALLBACK_HOST));
+    }
+    hosts.into_values().collect()
+}
+
+/// Where a remote connects over SSH: the ssh destination and port
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SshEndpoint {
+    /// `None` leaves the user to ssh_config, as git would
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl fmt::Display for SshEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(user) = &self.user {
+            write!(f, "{user}@")?;
+        }
+        match self.port {
+            Some(port) => write!(f, "{}:{port}", self.host),
+            None => write!(f, "{}", self.host),
+        }
+    }
+}
+
+/// The SSH endpoint `url` connects to once `scheme` rewriting applies, or
+/// `None` when it ends up on another transport
+pub fn ssh_endpoint(
+    url: &str,
+    scheme: Option<UrlScheme>,
+    configured: &[RewriteHost],
+) -> Option<SshEndpoint> {
+    let (kind, host, port) = parse_remote(url)?;
+    match (kind, scheme) {
+        (RemoteKind::Ssh { user }, None | Some(UrlScheme::Ssh)) => Some(SshEndpoint {
+            user,
+            host,
+            port: port.filter(|&port| port != 22),
+        }),
+        (RemoteKind::Https, Some(UrlScheme::Ssh)) => {
+            let rewritten = merge_hosts([url], configured)
+                .into_iter()
+                .find(|entry| entry.host == host)?;
+            Some(SshEndpoint {
+                user: Some(rewritten.ssh_user),
+                host,
+                port: rewritten.ssh_port,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// `url.<base>.insteadOf=<prefix>` values for `git -c`, for the hosts of
+/// `repo`'s remotes plus the configured ones
+pub fn rewrite_configs(repo: &Path, scheme: UrlScheme, configured: &[RewriteHost]) -> Vec<String> {
+    let urls: Vec<String> = remotes(repo).into_iter().map(|remote| remote.url).collect();
+    merge_hosts(urls.iter().map(String::as_str), configured)
+        .iter()
+        .flat_map(|host| host.rules(scheme))
+        .map(|(base, source)| format!("url.{base}.insteadOf={source}"))
+        .collect()
+}
+
+/// `url` as git would fetch it under `scheme`, for URLs that aren't a
+/// remote yet (clone): the longest matching `insteadOf` prefix is replaced
+pub fn rewrite_url(url: &str, scheme: UrlScheme, configured: &[RewriteHost]) -> String {
+    merge_hosts([url], configured)
+        .iter()
+        .flat_map(|host| host.rules(scheme))
+        .filter(|(_, source)| url.starts_with(source.as_str()))
+        .max_by_key(|(_, source)| source.len())
+        .map(|(base, source)| format!("{base}{}", &url[source.len()..]))
+        .unwrap_or_else(|| url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn configs(urls: &[&str], configured: &[&str], scheme: UrlScheme) -> Vec<String> {
+        let configured: Vec<RewriteHost> = configured
+            .iter()
+            .map(|entry| parse_url_host(entry).unwrap())
+            .collect();
+        merge_hosts(urls.iter().copied(), &configured)
+            .iter()
+            .flat_map(|host| host.rules(scheme))
+            .map(|(base, source)| format!("url.{base}.insteadOf={source}"))
+            .collect()
+    }
+
+    #[test]
+    fn test_github_fallback_matches_spec() {
+        assert_eq!(
+            configs(&[], &[], UrlScheme::Ssh),
+            vec!["url.git@github.com:.insteadOf=https://github.com/"]
+        );
+        assert_eq!(
+            configs(&["/srv/mirror/repo.git"], &[], UrlScheme::Https),
+            vec![
+                "url.https://github.com/.insteadOf=git@github.com:",
+                "url.https://github.com/.insteadOf=ssh://git@github.com/",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rules_follow_each_remote_host() {
+        let urls = [
+            "https://gitlab.internal/team/api.git",
+            "git@bitbucket.org:team/web.git",
+        ];
+        assert_eq!(
+            configs(&urls, &[], UrlScheme::Ssh),
+            vec![
+                "url.git@bitbucket.org:.insteadOf=https://bitbucket.org/",
+                "url.git@gitlab.internal:.insteadOf=https://gitlab.internal/",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ports_and_users_use_ssh_urls() {
+        let urls = [
+            "ssh://gitea@git.example.com:2222/team/api.git",
+            "https://git.example.com:3000/team/api.git",
+        ];
+        assert_eq!(
+            configs(&urls, &[], UrlScheme::Ssh),
+            vec![
+                "url.ssh://gitea@git.example.com:2222/.insteadOf=https://git.example.com/",
+                "url.ssh://gitea@git.example.com:2222/.insteadOf=https://git.example.com:3000/",
+            ]
+        );
+        assert_eq!(
+            configs(&urls, &[], UrlScheme::Https),
+            vec![
+                "url.https://git.example.com:3000/.insteadOf=gitea@git.example.com:",
+                "url.https://git.example.com:3000/.insteadOf=ssh://gitea@git.example.com/",
+                "url.https://git.example.com:3000/.insteadOf=ssh://gitea@git.example.com:2222/",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_configured_hosts_add_and_override() {
+        assert_eq!(
+            configs(
+                &["https://gitlab.internal/team/api.git"],
+                &["gitlab.internal:2222", "ops@gitea.lan"],
+                UrlScheme::Ssh
+            ),
+            vec![
+                "url.ops@gitea.lan:.insteadOf=https://gitea.lan/",
+                "url.ssh://git@gitlab.internal:2222/.insteadOf=https://gitlab.internal/",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ssh_endpoint_follows_rewriting() {
+        let endpoint = |url, scheme| ssh_endpoint(url, scheme, &[]).map(|e| e.to_string());
+        let ssh = Some(UrlScheme::Ssh);
+        assert_eq!(
+            endpoint("git@github.com:org/a.git", None).as_deref(),
+            Some("git@github.com")
+        );
+        assert_eq!(
+            endpoint("ssh://host.lan:2222/a.git", ssh).as_deref(),
+            Some("host.lan:2222")
+        );
+        assert_eq!(
+            endpoint("https://github.com/org/a.git", ssh).as_deref(),
+            Some("git@github.com")
+        );
+        assert_eq!(endpoint("https://github.com/org/a.git", None), None);
+        assert_eq!(
+            endpoint("git@github.com:org/a.git", Some(UrlScheme::Https)),
+            None
+        );
+        assert_eq!(endpoint("/srv/mirror/a.git", None), None);
+
+        let configured = [parse_url_host("ops@gitea.lan:2222").unwrap()];
+        assert_eq!(
+            ssh_endpoint("https://gitea.lan/a.git", ssh, &configured).map(|e| e.to_string()),
+            Some("ops@gitea.lan:2222".to_string())
+        );
+    }
+
+    #[test]
+    fn test_rewrite_url() {
+        assert_eq!(
+            rewrite_url("https://github.com/org/a.git", UrlScheme::Ssh, &[]),
+            "git@github.com:org/a.git"
+        );
+        assert_eq!(
+            rewrite_url(
+                "ssh://gitea@git.example.com:2222/team/api.git",
+                UrlScheme::Https,
+                &[]
+            ),
+            "https://git.example.com/team/api.git"
+        );
+        let configured = [parse_url_host("gitlab.internal:2222").unwrap()];
+        assert_eq!(
+            rewrite_url(
+                "https://gitlab.internal/team/api.git",
+                UrlScheme::Ssh,
+                &configured
+            ),
+            "ssh://git@gitlab.internal:2222/team/api.git"
+        );
+        assert_eq!(
+            rewrite_url("git@github.com:org/a.git", UrlScheme::Ssh, &[]),
+            "git@github.com:org/a.git"
+        );
+        assert_eq!(
+            rewrite_url("/srv/mirror/a.git", UrlScheme::Https, &[]),
+            "/srv/mirror/a.git"
+        );
+    }
+
+    #[test]
+    fn test_parse_url_host() {
+        assert_eq!(
+            parse_url_host("GitLab.Internal"),
+            Ok(RewriteHost::new("gitlab.internal"))
+        );
+        let entry = parse_url_host("gitea@[::1]:2222").unwrap();
+        assert_eq!(entry.host, "::1");
+        assert_eq!(entry.ssh_user, "gitea");
+        assert_eq!(entry.ssh_port, Some(2222));
+        assert_eq!(entry.ssh_base(), "ssh://gitea@[::1]:2222/");
+        assert_eq!(entry.to_string(), "gitea@[::1]:2222");
+        assert!(parse_url_host("host:ssh").is_err());
+        assert!(parse_url_host("@host").is_err());
+        assert!(parse_url_host("https://host/").is_err());
+    }
+}
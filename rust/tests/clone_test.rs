@@ -0,0 +1,163 @@
+mod common;
+
+use common::{commit, git, rows, run};
+
+/// Bare `remote/api.git` and `remote/web.git` with one commit each, and an
+/// empty `ws/` to clone into
+fn workspace() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().expect("temp dir");
+    let root = temp.path();
+    for name in ["api", "web"] {
+        git(root, &["init", "-q", "-b", "main", &format!("seed-{name}")]);
+        let seed = root.join(format!("seed-{name}"));
+        commit(&seed, name);
+        git(
+            root,
+            &[
+                "clone",
+                "-q",
+                "--bare",
+                &format!("seed-{name}"),
+                &format!("remote/{name}.git"),
+            ],
+        );
+    }
+    std::fs::create_dir(root.join("ws")).unwrap();
+    temp
+}
+
+#[test]
+fn clone_skips_existing_checkouts_and_reports_errors() {
+    let temp = workspace();
+    let remote = temp.path().join("remote");
+    let ws = temp.path().join("ws");
+    git(&ws, &["clone", "-q", "../remote/web.git", "web"]);
+    std::fs::write(
+        ws.join("repos.txt"),
+        format!(
+            "# team repos\n{api} services/api\n{web}\n{gone}\n",
+            api = remote.join("api.git").display(),
+            web = remote.join("web.git").display(),
+            gone = remote.join("gone.git").display(),
+        ),
+    )
+    .unwrap();
+
+    // The existing checkout runs no git at all
+    let output = run(&ws, &["--dry-run", "clone", "repos.txt"]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.matches(" clone ").count(), 2, "{stdout}");
+    assert!(
+        !stdout.contains(&ws.join("web").display().to_string()),
+        "{stdout}"
+    );
+
+    let output = run(&ws, &["clone", "repos.txt"]);
+    assert!(!output.status.success(), "missing remote should fail");
+    let rows = rows(&output);
+    assert_eq!(rows.len(), 3, "{rows:?}");
+    assert_eq!(rows[0].0, "gone");
+    assert!(rows[0].1.starts_with("fatal: "), "{rows:?}");
+    assert_eq!(rows[1], ("services/api".to_string(), "cloned".to_string()));
+    assert_eq!(rows[2], ("web".to_string(), "exists".to_string()));
+
+    assert_eq!(
+        git(&ws.join("services/api"), &["rev-parse", "HEAD"]),
+        git(&remote.join("api.git"), &["rev-parse", "main"])
+    );
+    assert!(!ws.join("gone").exists());
+}
+
+#[test]
+fn clone_reads_yaml_and_reports_json_fields() {
+    let temp = workspace();
+    let remote = temp.path().join("remote");
+    let ws = temp.path().join("ws");
+    std::fs::write(
+        temp.path().join("team.yaml"),
+        format!(
+            "repos:\n  - {api}\n  - url: {web}\n    path: apps/web\n",
+            api = remote.join("api.git").display(),
+            web = remote.join("web.git").display(),
+        ),
+    )
+    .unwrap();
+
+    let output = run(&ws, &["--format", "json", "clone", "../team.yaml"]);
+    assert!(output.status.success(), "{output:?}");
+    let records: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    let names: Vec<&str> = records
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|record| record["repo"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["api", "apps/web"]);
+    assert_eq!(records[0]["fields"]["clone"], "cloned");
+    assert!(ws.join("apps/web/.git").is_dir());
+
+    let output = run(&ws, &["--format", "json", "clone", "../team.yaml"]);
+    assert!(output.status.success(), "{output:?}");
+    let records: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    assert_eq!(records[1]["fields"]["clone"], "exists");
+}
+
+#[test]
+fn clone_rewrites_urls_for_ssh() {
+    let temp = workspace();
+    let ws = temp.path().join("ws");
+    std::fs::write(
+        ws.join("repos.toml"),
+        "[[repos]]\nurl = \"https://github.com/org/api.git\"\n\n[[repos]]\nurl = \"https://gitlab.internal/team/web.git\"\npath = \"team/web\"\n",
+    )
+    .unwrap();
+
+    let output = run(
+        &ws,
+        &[
+            "--dry-run",
+            "--ssh",
+            "--url-host",
+            "gitlab.internal:2222",
+            "clone",
+            "repos.toml",
+            "--depth",
+            "1",
+        ],
+    );
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(&format!(
+            "clone --depth 1 git@github.com:org/api.git {}",
+            ws.join("api").display()
+        )),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains(&format!(
+            "clone --depth 1 ssh://git@gitlab.internal:2222/team/web.git {}",
+            ws.join("team/web").display()
+        )),
+        "{stdout}"
+    );
+    assert!(!ws.join("api").exists());
+}
+
+#[test]
+fn fresh_clones_show_their_checked_out_branch() {
+    let temp = workspace();
+    let ws = temp.path().join("ws");
+    std::fs::write(
+        ws.join("repos.txt"),
+        format!("{}\n", temp.path().join("remote/api.git").display()),
+    )
+    .unwrap();
+
+    let output = run(&ws, &["clone", "repos.txt"]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let columns: Vec<&str> = stdout.trim().split(" | ").map(str::trim).collect();
+    assert_eq!(columns, ["api", "main", "cloned"], "{stdout}");
+}
@@ -0,0 +1,91 @@
+//! Helpers shared by the integration tests. Each test file builds its own
+//! `workspace()` from these.
+
+#![allow(dead_code)]
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Run git in `dir` with a fixed identity, returning its trimmed stdout
+pub fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+        .args(["-c", "protocol.file.allow=always"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("git should run");
+    assert!(
+        output.status.success(),
+        "git {args:?} failed in {}: {}",
+        dir.display(),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// An empty commit in the repo at `dir`
+pub fn commit(dir: &Path, message: &str) {
+    git(dir, &["commit", "-q", "--allow-empty", "-m", message]);
+}
+
+/// An empty repo on `main` at `parent/name`, creating missing directories
+pub fn make_repo(parent: &Path, name: &str) {
+    let status = Command::new("git")
+        .args(["init", "-q", "-b", "main"])
+        .arg(parent.join(name))
+        .status()
+        .expect("git init");
+    assert!(status.success());
+}
+
+/// A repo at `parent/name` where `git check` runs `alias`
+pub fn make_repo_with_alias(parent: &Path, name: &str, alias: &str) {
+    make_repo(parent, name);
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(parent.join(name))
+        .args(["config", "alias.check", alias])
+        .status()
+        .expect("git config");
+    assert!(status.success());
+}
+
+/// Run git-all in `dir`, with `dir` as its home so no user config applies
+pub fn run(dir: &Path, args: &[&str]) -> Output {
+    run_with_env(dir, args, &[])
+}
+
+/// [`run`] with extra environment variables set
+pub fn run_with_env(dir: &Path, args: &[&str], vars: &[(&str, &Path)]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_git-all"))
+        .args(args)
+        .envs(vars.iter().copied())
+        .current_dir(dir)
+        .env("HOME", dir)
+        .env_remove("XDG_CONFIG_HOME")
+        .output()
+        .expect("git-all should run")
+}
+
+/// The repo column of text output, one entry per line
+pub fn repo_column(output: &Output) -> Vec<String> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.split(" | ").next().unwrap_or("").trim().to_string())
+        .collect()
+}
+
+/// `(repo, message)` pairs from text output
+pub fn rows(output: &Output) -> Vec<(String, String)> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split(" | ").map(str::trim).collect();
+            match columns[..] {
+                [repo, _, message] => Some((repo.to_string(), message.to_string())),
+                _ => None,
+            }
+        })
+        .collect()
+}
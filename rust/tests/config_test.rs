@@ -0,0 +1,114 @@
+mod common;
+
+use common::{make_repo, repo_column};
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// A home directory with a user config and a workspace below it
+struct Fixture {
+    temp: tempfile::TempDir,
+}
+
+impl Fixture {
+    fn new(user_config: &str, workspace_config: &str) -> Self {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let config_dir = temp.path().join("home/.config/git-all");
+        fs::create_dir_all(&config_dir).unwrap();
+        fs::write(config_dir.join("config.toml"), user_config).unwrap();
+
+        let workspace = temp.path().join("src");
+        make_repo(&workspace, "app");
+        make_repo(&workspace, "archive/old");
+        make_repo(&workspace, "nested/lib");
+        fs::write(workspace.join(".git-all.toml"), workspace_config).unwrap();
+        Fixture { temp }
+    }
+
+    fn workspace(&self) -> std::path::PathBuf {
+        self.temp.path().join("src")
+    }
+
+    fn run(&self, dir: &Path, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_git-all"))
+            .args(args)
+            .current_dir(dir)
+            .env("HOME", self.temp.path().join("home"))
+            .env_remove("XDG_CONFIG_HOME")
+            .env_remove("GIT_ALL_RETRIES")
+            .env_remove("GIT_ALL_TIMEOUT")
+            .output()
+            .expect("git-all should run")
+    }
+}
+
+#[test]
+fn workspace_config_overrides_user_config() {
+    let fixture = Fixture::new(
+        "scan-depth = \"all\"\nexclude = [\"nested\"]\n",
+        "exclude = [\"archive\"]\n",
+    );
+
+    let output = fixture.run(&fixture.workspace(), &["status"]);
+    assert!(output.status.success(), "{output:?}");
+    // scan-depth comes from the user config; exclude from the workspace
+    assert_eq!(repo_column(&output), ["app", "nested/lib"]);
+}
+
+#[test]
+fn workspace_config_is_found_from_subdirectories() {
+    let fixture = Fixture::new("", "scan-depth = \"all\"\n");
+
+    let output = fixture.run(&fixture.workspace().join("nested"), &["status"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(repo_column(&output), ["lib"]);
+}
+
+#[test]
+fn command_line_overrides_config() {
+    let fixture = Fixture::new("", "scan-depth = \"all\"\nexclude = [\"archive\"]\n");
+
+    let output = fixture.run(
+        &fixture.workspace(),
+        &["--scan-depth", "1", "--exclude", "nested", "status"],
+    );
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(repo_column(&output), ["app"]);
+}
+
+#[test]
+fn meta_config_reports_sources() {
+    let fixture = Fixture::new("workers = 4\ntimeout = \"45s\"\n", "workers = 12\n");
+
+    let output = fixture.run(
+        &fixture.workspace(),
+        &["--format", "ndjson", "meta", "config"],
+    );
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = |key: &str| {
+        stdout
+            .lines()
+            .find(|line| line.starts_with(&format!("{key} =")))
+            .unwrap_or_else(|| panic!("no {key} line in:\n{stdout}"))
+            .to_string()
+    };
+
+    assert!(line("workers").contains("= 12"));
+    assert!(line("workers").contains("# workspace config"));
+    assert!(line("timeout").contains("\"45s\""));
+    assert!(line("timeout").contains("# user config"));
+    assert!(line("format").contains("# command line"));
+    assert!(line("retries").contains("# built-in"));
+}
+
+#[test]
+fn invalid_config_is_reported() {
+    let fixture = Fixture::new("", "wokers = 4\n");
+
+    let output = fixture.run(&fixture.workspace(), &["status"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(".git-all.toml"), "{stderr}");
+    assert!(stderr.contains("wokers"), "{stderr}");
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use common::{git, repo_column, run};
+use std::process::Command;
+
+/// `app` with a linked worktree `app-feature` and a `vendor/lib` submodule,
+/// `lib` as a plain repo, and `mirror.git` as a bare clone of it
+fn workspace() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().expect("temp dir");
+    let root = temp.path();
+    for name in ["app", "lib"] {
+        git(root, &["init", "-q", "-b", "main", name]);
+        git(
+            &root.join(name),
+            &["commit", "-q", "--allow-empty", "-m", "init"],
+        );
+    }
+    git(root, &["clone", "-q", "--bare", "lib", "mirror.git"]);
+    let app = root.join("app");
+    git(
+        &app,
+        &["worktree", "add", "-q", "-b", "feature", "../app-feature"],
+    );
+    git(&app, &["submodule", "add", "-q", "../lib", "vendor/lib"]);
+    temp
+}
+
+#[test]
+fn default_discovery_lists_worktrees_separately() {
+    let temp = workspace();
+    let output = run(temp.path(), &["status"]);
+
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(repo_column(&output), ["app", "app-feature", "lib"]);
+}
+
+#[test]
+fn worktrees_can_be_deduped_or_skipped() {
+    let temp = workspace();
+    for mode in ["dedupe", "skip"] {
+        let output = run(temp.path(), &["--worktrees", mode, "status"]);
+        assert!(output.status.success(), "{output:?}");
+        assert_eq!(repo_column(&output), ["app", "lib"], "--worktrees {mode}");
+    }
+}
+
+#[test]
+fn include_bare_adds_bare_repos() {
+    let temp = workspace();
+    let output = run(
+        temp.path(),
+        &[
+            "--include-bare",
+            "--worktrees",
+            "skip",
+            "rev-parse",
+            "--is-bare-repository",
+        ],
+    );
+
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("==> mirror.git <==\ntrue\n"), "{stdout}");
+}
+
+#[test]
+fn recurse_submodules_lists_them_under_their_parent() {
+    let temp = workspace();
+    let output = run(
+        temp.path(),
+        &["--recurse-submodules", "--worktrees", "skip", "status"],
+    );
+
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(repo_column(&output), ["app", "app/vendor/lib", "lib"]);
+}
+
+#[test]
+fn heavy_directories_are_pruned() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    let root = temp.path();
+    git(root, &["init", "-q", "-b", "main", "web"]);
+    git(root, &["init", "-q", "-b", "main", "site/node_modules/dep"]);
+
+    let output = run(root, &["--scan-depth", "all", "status"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(repo_column(&output), ["web"]);
+
+    let output = run(root, &["--scan-depth", "all", "--no-prune", "status"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(repo_column(&output), ["site/node_modules/dep", "web"]);
+}
+
+#[test]
+fn discovery_cache_notices_new_repos() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    let root = temp.path().join("src");
+    let cache = temp.path().join("cache");
+    git(temp.path(), &["init", "-q", "-b", "main", "src/team/api"]);
+    let cached_run = || {
+        Command::new(env!("CARGO_BIN_EXE_git-all"))
+            .args(["--scan-depth", "all", "--discovery-cache", "status"])
+            .current_dir(&root)
+            .env("HOME", temp.path())
+            .env("XDG_CACHE_HOME", &cache)
+            .env_remove("XDG_CONFIG_HOME")
+            .output()
+            .expect("git-all should run")
+    };
+
+    assert_eq!(repo_column(&cached_run()), ["team/api"]);
+    let entries = std::fs::read_dir(cache.join("git-all")).unwrap().count();
+    assert_eq!(entries, 1, "one cache file per scanned root");
+
+    git(&root, &["init", "-q", "-b", "main", "team/web"]);
+    assert_eq!(repo_column(&cached_run()), ["team/api", "team/web"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinked_repos_are_followed_unless_disabled() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    let workspace = temp.path().join("workspace");
+    git(temp.path(), &["init", "-q", "-b", "main", "workspace/app"]);
+    git(temp.path(), &["init", "-q", "-b", "main", "elsewhere"]);
+    std::os::unix::fs::symlink(temp.path().join("elsewhere"), workspace.join("linked"))
+        .expect("symlink");
+
+    for (args, expected) in [
+        (&["status"][..], &["app", "linked"][..]),
+        (&["--no-follow-symlinks", "status"], &["app"]),
+        (
+            &["--no-follow-symlinks", "--follow-symlinks", "status"],
+            &["app", "linked"],
+        ),
+    ] {
+        let output = run(&workspace, args);
+        assert!(output.status.success(), "{args:?}: {output:?}");
+        assert_eq!(repo_column(&output), expected, "{args:?}");
+    }
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::{make_repo_with_alias, run};
+
+#[test]
+fn empty_discovery_exits_9() {
+    let temp = tempfile::tempdir().expect("temp dir");
+
+    let output = run(temp.path(), &["status"]);
+
+    assert_eq!(output.status.code(), Some(9));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("No git repositories found in current directory"));
+}
+
+#[test]
+fn git_all_errors_and_usage_errors_exit_9() {
+    let temp = tempfile::tempdir().expect("temp dir");
+
+    let output = run(temp.path(), &["--root", "missing", "status"]);
+    assert_eq!(output.status.code(), Some(9));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("--root missing: not a directory"),
+        "{stderr}"
+    );
+
+    let output = run(temp.path(), &["--workers", "many", "status"]);
+    assert_eq!(output.status.code(), Some(9));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--workers"), "{stderr}");
+
+    let output = run(temp.path(), &["--help"]);
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn all_repos_succeeding_exits_0_without_summary() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    make_repo_with_alias(temp.path(), "a", "!true");
+    make_repo_with_alias(temp.path(), "b", "!true");
+
+    let output = run(temp.path(), &["check"]);
+
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("failed:"), "unexpected summary: {stdout}");
+}
+
+#[test]
+fn failed_repos_exit_1_with_summary() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    make_repo_with_alias(temp.path(), "a", "!exit 1");
+    make_repo_with_alias(temp.path(), "b", "!true");
+    make_repo_with_alias(temp.path(), "c", "!exit 3");
+
+    let output = run(temp.path(), &["check"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout.lines().last(),
+        Some("2 of 3 repositories failed: a, c"),
+        "expected trailing failure summary: {stdout}"
+    );
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::{make_repo, repo_column, run};
+use std::path::Path;
+use std::process::Output;
+
+fn workspace() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().expect("temp dir");
+    make_repo(temp.path(), "active");
+    make_repo(temp.path(), "archive/old");
+    make_repo(temp.path(), "work/app");
+    make_repo(temp.path(), "work/legacy-app");
+    temp
+}
+
+fn run_status(dir: &Path, args: &[&str]) -> Output {
+    run(dir, &[&["--scan-depth", "all"], args, &["status"]].concat())
+}
+
+#[test]
+fn exclude_skips_matching_repos() {
+    let temp = workspace();
+    let output = run_status(temp.path(), &["--exclude", "archive/*"]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        repo_column(&output),
+        ["active", "work/app", "work/legacy-app"]
+    );
+}
+
+#[test]
+fn include_and_exclude_combine() {
+    let temp = workspace();
+    let output = run_status(
+        temp.path(),
+        &["--include", "work/*", "--exclude", "*/legacy-*"],
+    );
+
+    assert!(output.status.success());
+    assert_eq!(repo_column(&output), ["work/app"]);
+}
+
+#[test]
+fn ignore_file_prunes_discovery() {
+    let temp = workspace();
+    std::fs::write(temp.path().join(".git-all-ignore"), "# retired\narchive\n").unwrap();
+    std::fs::write(temp.path().join("work/.git-all-ignore"), "legacy-*\n").unwrap();
+
+    let output = run_status(temp.path(), &[]);
+
+    assert!(output.status.success());
+    assert_eq!(repo_column(&output), ["active", "work/app"]);
+}
+
+#[test]
+fn filters_matching_nothing_exit_9() {
+    let temp = workspace();
+    let output = run_status(temp.path(), &["--include", "nope/*"]);
+
+    assert_eq!(output.status.code(), Some(9));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("No git repositories matched --include/--exclude"));
+}
+
+#[test]
+fn invalid_glob_is_a_usage_error() {
+    let temp = workspace();
+    let output = run_status(temp.path(), &["--exclude", "archive/["]);
+
+    assert_eq!(output.status.code(), Some(9));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--exclude"), "{stderr}");
+}
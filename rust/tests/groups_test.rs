@@ -0,0 +1,100 @@
+mod common;
+
+use common::{make_repo, repo_column, run};
+use std::fs;
+
+const GROUPS: &str = r#"
+[groups]
+backend = ["services/api", "services/*-worker"]
+mobile = ["apps/ios", "apps/android"]
+legacy = ["services/old-worker"]
+"#;
+
+fn workspace() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().expect("temp dir");
+    for name in [
+        "services/api",
+        "services/billing-worker",
+        "services/old-worker",
+        "apps/ios",
+        "docs",
+    ] {
+        make_repo(temp.path(), name);
+    }
+    fs::write(temp.path().join(".git-all.toml"), GROUPS).unwrap();
+    temp
+}
+
+#[test]
+fn group_replaces_discovery() {
+    let temp = workspace();
+    let output = run(temp.path(), &["--group", "backend", "status"]);
+
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        repo_column(&output),
+        [
+            "services/api",
+            "services/billing-worker",
+            "services/old-worker"
+        ]
+    );
+}
+
+#[test]
+fn groups_combine_with_union_and_exclusion() {
+    let temp = workspace();
+    let output = run(
+        temp.path(),
+        &["--group", "backend,mobile", "--group", "!legacy", "status"],
+    );
+
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        repo_column(&output),
+        ["apps/ios", "services/api", "services/billing-worker"]
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("group mobile: skipping apps/android"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn exclusion_alone_filters_discovery() {
+    let temp = workspace();
+    let output = run(
+        temp.path(),
+        &["--scan-depth", "2", "--group", "!backend", "status"],
+    );
+
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(repo_column(&output), ["apps/ios", "docs"]);
+}
+
+#[test]
+fn unknown_group_fails() {
+    let temp = workspace();
+    let output = run(temp.path(), &["--group", "web", "status"]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("unknown group \"web\""), "{stderr}");
+}
+
+#[test]
+fn meta_groups_lists_members_and_flags_missing_ones() {
+    let temp = workspace();
+    let output = run(temp.path(), &["meta", "groups"]);
+
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("backend  # workspace config"), "{stdout}");
+    assert!(stdout.contains("  services/billing-worker\n"), "{stdout}");
+    assert!(stdout.contains("  apps/ios\n"), "{stdout}");
+    assert!(
+        stdout.contains("  apps/android  (!) ") && stdout.contains("does not exist"),
+        "{stdout}"
+    );
+}
@@ -0,0 +1,76 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use common::{git, make_repo, run_with_env};
+
+/// Repos whose origin lives on `limited.example`, plus a fake ssh that flags
+/// any overlap between concurrent connections before failing the fetch
+fn setup(temp: &Path) -> PathBuf {
+    let workspace = temp.join("workspace");
+    for name in ["a", "b", "c"] {
+        make_repo(&workspace, name);
+        git(
+            &workspace.join(name),
+            &["remote", "add", "origin", "ssh://git@limited.example/x.git"],
+        );
+    }
+
+    let fake_ssh = temp.join("fake-ssh");
+    std::fs::write(
+        &fake_ssh,
+        format!(
+            "#!/bin/sh\n\
+             mkdir '{lock}' 2>/dev/null || touch '{overlap}'\n\
+             sleep 0.3\n\
+             rmdir '{lock}' 2>/dev/null\n\
+             exit 1\n",
+            lock = temp.join("lock").display(),
+            overlap = temp.join("overlap").display(),
+        ),
+    )
+    .expect("write fake ssh");
+    Command::new("chmod")
+        .arg("+x")
+        .arg(&fake_ssh)
+        .status()
+        .expect("chmod");
+
+    workspace
+}
+
+fn fetch(temp: &Path, workspace: &Path, extra_args: &[&str]) {
+    let args = [extra_args, &["fetch"]].concat();
+    let fake_ssh = temp.join("fake-ssh");
+    run_with_env(workspace, &args, &[("GIT_SSH_COMMAND", &fake_ssh)]);
+}
+
+#[test]
+fn host_workers_limits_connections_per_host() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    let workspace = setup(temp.path());
+
+    fetch(
+        temp.path(),
+        &workspace,
+        &["--host-workers", "limited.example=1"],
+    );
+
+    assert!(
+        !temp.path().join("overlap").exists(),
+        "fetches against limited.example overlapped"
+    );
+}
+
+#[test]
+fn without_host_workers_connections_overlap() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    let workspace = setup(temp.path());
+
+    fetch(temp.path(), &workspace, &[]);
+
+    assert!(temp.path().join("overlap").exists());
+}
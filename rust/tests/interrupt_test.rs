@@ -0,0 +1,75 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::make_repo_with_alias;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Run `git-all <args>`, send SIGINT after `delay`, and return its output
+fn run_and_interrupt(dir: &std::path::Path, args: &[&str], delay: Duration) -> (i32, String) {
+    let child = Command::new(env!("CARGO_BIN_EXE_git-all"))
+        .args(args)
+        .current_dir(dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("git-all should run");
+
+    std::thread::sleep(delay);
+    // SAFETY: plain kill(2) on our own child's pid
+    unsafe {
+        libc::kill(child.id() as libc::pid_t, libc::SIGINT);
+    }
+
+    let started = Instant::now();
+    let output = child.wait_with_output().expect("wait for git-all");
+    assert!(
+        started.elapsed() < Duration::from_secs(5),
+        "git-all took {:?} to exit after SIGINT",
+        started.elapsed()
+    );
+
+    (
+        output.status.code().expect("exit code"),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+    )
+}
+
+#[test]
+fn ctrl_c_reports_completed_and_interrupted_repos() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    make_repo_with_alias(temp.path(), "fast", "!true");
+    // Background sleep checks the whole process group is terminated
+    make_repo_with_alias(temp.path(), "slow", "!sleep 10 & sleep 10");
+
+    let (code, stdout) = run_and_interrupt(temp.path(), &["check"], Duration::from_millis(500));
+
+    assert_eq!(code, 130);
+    assert!(
+        stdout.contains("Interrupted: 1 completed, 1 interrupted, 0 never started"),
+        "{stdout}"
+    );
+    assert!(stdout.contains("  completed: fast"), "{stdout}");
+    assert!(stdout.contains("  interrupted: slow"), "{stdout}");
+}
+
+#[test]
+fn ctrl_c_stops_queued_repos_from_starting() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    for name in ["a", "b", "c"] {
+        make_repo_with_alias(temp.path(), name, "!sleep 10");
+    }
+
+    let (code, stdout) = run_and_interrupt(
+        temp.path(),
+        &["--workers", "1", "check"],
+        Duration::from_millis(500),
+    );
+
+    assert_eq!(code, 130);
+    assert!(
+        stdout.contains("Interrupted: 0 completed, 1 interrupted, 2 never started"),
+        "{stdout}"
+    );
+}
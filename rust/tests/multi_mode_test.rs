@@ -0,0 +1,85 @@
+mod common;
+
+use common::{make_repo, repo_column};
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// A meta-repo `meta` holding independent repos `meta/a` and `meta/b`
+fn workspace() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().expect("temp dir");
+    for name in ["meta", "meta/a", "meta/b"] {
+        make_repo(temp.path(), name);
+    }
+    temp
+}
+
+fn run(dir: &Path, args: &[&str], mode: Option<&str>) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_git-all"));
+    command
+        .args(args)
+        .current_dir(dir)
+        .env("HOME", dir)
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("GIT_ALL_MODE");
+    if let Some(mode) = mode {
+        command.env("GIT_ALL_MODE", mode);
+    }
+    command.output().expect("git-all should run")
+}
+
+#[test]
+fn inside_a_repo_passes_through_by_default() {
+    let temp = workspace();
+    let output = run(&temp.path().join("meta"), &["status"], None);
+
+    assert!(output.status.success(), "{output:?}");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("On branch main"));
+}
+
+#[test]
+fn all_flag_fans_out_from_inside_a_repo() {
+    let temp = workspace();
+    let output = run(&temp.path().join("meta"), &["--all", "status"], None);
+
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(repo_column(&output), ["a", "b"]);
+}
+
+#[test]
+fn mode_env_fans_out_from_inside_a_repo() {
+    let temp = workspace();
+    let output = run(&temp.path().join("meta"), &["status"], Some("multi"));
+
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(repo_column(&output), ["a", "b"]);
+
+    let output = run(&temp.path().join("meta"), &["status"], Some("sideways"));
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("GIT_ALL_MODE"));
+}
+
+#[test]
+fn all_after_the_command_belongs_to_git() {
+    let temp = workspace();
+    let output = run(&temp.path().join("meta"), &["branch", "--all"], None);
+
+    assert!(output.status.success(), "{output:?}");
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("==>"));
+}
+
+#[test]
+fn root_starts_discovery_elsewhere() {
+    let temp = workspace();
+    let output = run(
+        &temp.path().join("meta/a"),
+        &["--root", "..", "status"],
+        None,
+    );
+
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(repo_column(&output), ["a", "b"]);
+
+    let output = run(temp.path(), &["--root", "missing", "status"], None);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not a directory"));
+}
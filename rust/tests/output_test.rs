@@ -0,0 +1,92 @@
+mod common;
+
+use common::{make_repo, run};
+
+#[test]
+fn non_tty_status_prints_plain_columns_in_order() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    make_repo(temp.path(), "beta");
+    make_repo(temp.path(), "alpha");
+
+    let output = run(temp.path(), &["status"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        !stdout.contains('\x1b'),
+        "redirected output must not contain ANSI escapes: {stdout:?}"
+    );
+    assert_eq!(
+        stdout, "alpha | main | clean\nbeta  | main | clean\n",
+        "expected one plain row per repo in alpha order"
+    );
+}
+
+#[test]
+fn json_format_prints_array_of_records_in_order() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    make_repo(temp.path(), "beta");
+    make_repo(temp.path(), "alpha");
+    std::fs::write(temp.path().join("beta/new.txt"), "x").expect("write file");
+
+    let output = run(temp.path(), &["--format", "json", "status"]);
+
+    assert!(output.status.success());
+    let records: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout should be a JSON document");
+    let records = records.as_array().expect("top-level array");
+    assert_eq!(records.len(), 2);
+
+    assert_eq!(records[0]["repo"], "alpha");
+    assert_eq!(records[0]["summary"], "clean");
+    assert_eq!(records[1]["repo"], "beta");
+    assert_eq!(records[1]["branch"], "main");
+    assert_eq!(records[1]["exit_code"], 0);
+    assert_eq!(records[1]["summary"], "1 untracked");
+    assert_eq!(records[1]["fields"]["untracked"], 1);
+    assert!(
+        records[1]["path"]
+            .as_str()
+            .is_some_and(|path| path.ends_with("beta")),
+        "expected absolute path: {}",
+        records[1]["path"]
+    );
+    assert!(
+        records[1]["stdout"]
+            .as_str()
+            .is_some_and(|stdout| stdout.contains("?? new.txt")),
+        "expected raw stdout: {}",
+        records[1]["stdout"]
+    );
+    let command: Vec<&str> = records[1]["command"]
+        .as_array()
+        .expect("command argv")
+        .iter()
+        .filter_map(|arg| arg.as_str())
+        .collect();
+    assert_eq!(command.first(), Some(&"git"));
+    assert!(command.ends_with(&["status", "--porcelain", "-b"]));
+}
+
+#[test]
+fn ndjson_format_prints_one_record_per_line() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    make_repo(temp.path(), "alpha");
+    make_repo(temp.path(), "beta");
+    make_repo(temp.path(), "gamma");
+
+    let output = run(temp.path(), &["--format", "ndjson", "status"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut repos: Vec<String> = stdout
+        .lines()
+        .map(|line| {
+            let record: serde_json::Value =
+                serde_json::from_str(line).expect("each line should be a JSON record");
+            record["repo"].as_str().expect("repo name").to_string()
+        })
+        .collect();
+    repos.sort();
+    assert_eq!(repos, ["alpha", "beta", "gamma"]);
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::{make_repo_with_alias, run};
+
+#[test]
+fn passthrough_prints_full_output_under_repo_labels() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    make_repo_with_alias(temp.path(), "b", r#"!printf 'b one\nb two\n'"#);
+    make_repo_with_alias(temp.path(), "a", r#"!printf 'a one\n  a two\na three\n'"#);
+
+    let output = run(temp.path(), &["check"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        stdout,
+        "==> a <==\na one\n  a two\na three\n==> b <==\nb one\nb two\n"
+    );
+}
+
+#[test]
+fn passthrough_keeps_stderr_unmodified() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    make_repo_with_alias(
+        temp.path(),
+        "a",
+        r#"!printf 'warning: first\nwarning: second\n' >&2"#,
+    );
+
+    let output = run(temp.path(), &["check"]);
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "==> a <==\n");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "warning: first\nwarning: second\n"
+    );
+}
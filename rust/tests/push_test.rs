@@ -0,0 +1,98 @@
+mod common;
+
+use common::{commit, git, rows, run};
+
+/// A bare `upstream.git` and clones of it under `ws/` in every state push reports
+fn workspace() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().expect("temp dir");
+    let root = temp.path();
+    git(
+        root,
+        &["init", "-q", "--bare", "-b", "main", "upstream.git"],
+    );
+    git(root, &["clone", "-q", "upstream.git", "seed"]);
+    commit(&root.join("seed"), "one");
+    git(&root.join("seed"), &["push", "-q", "origin", "main"]);
+
+    git(root, &["clone", "-q", "upstream.git", "ws/stale"]);
+    commit(&root.join("ws/stale"), "mine");
+    commit(&root.join("seed"), "theirs");
+    git(&root.join("seed"), &["push", "-q", "origin", "main"]);
+
+    for name in ["ahead", "current", "feature"] {
+        git(
+            root,
+            &["clone", "-q", "upstream.git", &format!("ws/{name}")],
+        );
+    }
+    commit(&root.join("ws/ahead"), "two");
+    commit(&root.join("ws/ahead"), "three");
+    git(&root.join("ws/feature"), &["checkout", "-q", "-b", "feat"]);
+    commit(&root.join("ws/feature"), "feat");
+    temp
+}
+
+#[test]
+fn push_summarizes_each_repo() {
+    let temp = workspace();
+    let ws = temp.path().join("ws");
+
+    let output = run(&ws, &["push"]);
+    assert!(!output.status.success(), "rejected push should fail");
+    let rows = rows(&output);
+    let expected = [
+        ("ahead", "pushed main (2 commits)"),
+        ("current", "up to date"),
+        ("feature", "no upstream"),
+        ("stale", "rejected: fetch first"),
+    ];
+    let expected: Vec<(String, String)> = expected
+        .iter()
+        .map(|(repo, message)| (repo.to_string(), message.to_string()))
+        .collect();
+    assert_eq!(rows, expected);
+
+    assert_eq!(
+        git(&temp.path().join("upstream.git"), &["rev-parse", "main"]),
+        git(&ws.join("ahead"), &["rev-parse", "HEAD"])
+    );
+}
+
+#[test]
+fn push_reports_json_fields() {
+    let temp = workspace();
+    let ws = temp.path().join("ws");
+    let output = run(&ws, &["--format", "json", "--include", "ahead", "push"]);
+    assert!(output.status.success(), "{output:?}");
+
+    let records: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    let refs = &records[0]["fields"]["refs"][0];
+    assert_eq!(refs["ref"], "refs/heads/main");
+    assert_eq!(refs["result"], "pushed");
+    assert_eq!(refs["commits"], 2);
+    let stdout = records[0]["stdout"].as_str().unwrap();
+    assert!(
+        stdout.contains("refs/heads/main:refs/heads/main"),
+        "{stdout}"
+    );
+    assert!(!stdout.contains("git-all"), "{stdout}");
+}
+
+#[test]
+fn only_ahead_skips_repos_with_nothing_to_push() {
+    let temp = workspace();
+    let ws = temp.path().join("ws");
+
+    let output = run(&ws, &["--dry-run", "--only-ahead", "push"]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(
+            "Excluded 2 repositories by selection:\n  current: nothing to push\n  feature: no upstream\n"
+        ),
+        "{stdout}"
+    );
+    assert!(stdout.contains("ahead push --porcelain"), "{stdout}");
+    assert!(stdout.contains("stale push --porcelain"), "{stdout}");
+    assert!(!stdout.contains("current push"), "{stdout}");
+}
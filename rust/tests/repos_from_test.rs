@@ -0,0 +1,108 @@
+mod common;
+
+use common::{make_repo, repo_column};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+fn workspace() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().expect("temp dir");
+    for name in ["api", "web", "tools/lint"] {
+        make_repo(temp.path(), name);
+    }
+    temp
+}
+
+fn run(dir: &Path, args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_git-all"))
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("git-all should run");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn reads_paths_from_stdin() {
+    let temp = workspace();
+    let output = run(
+        temp.path(),
+        &["--repos-from", "-", "status"],
+        "web\n# not this one\ntools/lint\nmissing\n",
+    );
+
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(repo_column(&output), ["tools/lint", "web"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("missing (does not exist)"));
+}
+
+#[test]
+fn reads_repo_manifest_relative_to_checkout_root() {
+    let temp = workspace();
+    let manifests = temp.path().join(".repo/manifests");
+    fs::create_dir_all(&manifests).unwrap();
+    fs::write(
+        manifests.join("default.xml"),
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest>
+  <project name="org/api" path="api"/>
+  <project name="tools/lint"/>
+</manifest>
+"#,
+    )
+    .unwrap();
+
+    let output = run(
+        temp.path(),
+        &["--repos-from", ".repo/manifests/default.xml", "status"],
+        "",
+    );
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(repo_column(&output), ["api", "tools/lint"]);
+}
+
+#[test]
+fn reads_mrconfig() {
+    let temp = workspace();
+    fs::write(
+        temp.path().join(".mrconfig"),
+        "[DEFAULT]\njobs = 2\n\n[api]\ncheckout = git clone git@example.com:api.git api\n\n[web]\n",
+    )
+    .unwrap();
+
+    let output = run(temp.path(), &["--repos-from", ".mrconfig", "status"], "");
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(repo_column(&output), ["api", "web"]);
+}
+
+#[test]
+fn repo_list_does_not_pass_through_inside_a_repo() {
+    let temp = workspace();
+    let output = run(
+        &temp.path().join("api"),
+        &["--repos-from", "-", "status"],
+        "../web\n",
+    );
+
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(repo_column(&output), ["web"]);
+}
+
+#[test]
+fn empty_list_exits_9() {
+    let temp = workspace();
+    let output = run(temp.path(), &["--repos-from", "-", "status"], "\n");
+
+    assert_eq!(output.status.code(), Some(9));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No git repositories found"));
+}
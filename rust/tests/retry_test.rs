@@ -0,0 +1,86 @@
+mod common;
+
+use common::{make_repo_with_alias, run};
+use std::process::Command;
+
+/// Fails with a transient SSH error on the first run, then succeeds
+const FLAKY_ALIAS: &str = "!if [ -e .git/tried ]; then echo ok; \
+    else touch .git/tried; echo 'Connection reset by peer' >&2; exit 128; fi";
+
+fn records(stdout: &[u8]) -> Vec<serde_json::Value> {
+    String::from_utf8_lossy(stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("ndjson record"))
+        .collect()
+}
+
+#[test]
+fn transient_failure_is_retried_and_reported() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    make_repo_with_alias(temp.path(), "flaky", FLAKY_ALIAS);
+    let trace_path = temp.path().join("trace.log");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_git-all"))
+        .args(["--retries", "2", "--format", "ndjson", "check"])
+        .env("GIT_ALL_TRACE_FILE", &trace_path)
+        .current_dir(temp.path())
+        .output()
+        .expect("git-all should run");
+
+    assert_eq!(output.status.code(), Some(0));
+    let records = records(&output.stdout);
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["success"], true);
+    assert_eq!(records[0]["attempts"], 2);
+    assert_eq!(records[0]["summary"], "ok (after 1 retry)");
+
+    let trace = std::fs::read_to_string(&trace_path).expect("trace file");
+    assert!(trace.contains("attempts=2"), "{trace}");
+}
+
+#[test]
+fn permanent_failure_is_not_retried() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    make_repo_with_alias(
+        temp.path(),
+        "denied",
+        "!echo 'ERROR: Permission denied' >&2; exit 128",
+    );
+
+    let output = run(
+        temp.path(),
+        &["--retries", "2", "--format", "ndjson", "check"],
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    let records = records(&output.stdout);
+    assert_eq!(records[0]["attempts"], 1);
+}
+
+#[test]
+fn retry_on_adds_custom_patterns() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    make_repo_with_alias(
+        temp.path(),
+        "busy",
+        "!if [ -e .git/tried ]; then echo ok; \
+         else touch .git/tried; echo 'remote: server busy' >&2; exit 1; fi",
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_git-all"))
+        .args([
+            "--retries",
+            "1",
+            "--retry-on",
+            "Server Busy",
+            "--format",
+            "ndjson",
+            "check",
+        ])
+        .current_dir(temp.path())
+        .output()
+        .expect("git-all should run");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(records(&output.stdout)[0]["attempts"], 2);
+}
@@ -0,0 +1,114 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::make_repo;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// A fake home with `src` and `work` roots and a `git-all-roots` symlink
+struct Fixture {
+    temp: tempfile::TempDir,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let temp = tempfile::tempdir().expect("temp dir");
+        let home = temp.path().join("home");
+        make_repo(&home, "src/api");
+        make_repo(&home, "src/web");
+        make_repo(&home, "work/client-app");
+
+        let bin = temp.path().join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::os::unix::fs::symlink(env!("CARGO_BIN_EXE_git-all"), bin.join("git-all-roots"))
+            .unwrap();
+        Fixture { temp }
+    }
+
+    fn home(&self) -> PathBuf {
+        self.temp.path().join("home").canonicalize().unwrap()
+    }
+
+    fn roots(&self, dir: &Path, args: &[&str]) -> Output {
+        Command::new(self.temp.path().join("bin/git-all-roots"))
+            .args(args)
+            .current_dir(dir)
+            .env("HOME", self.home())
+            .env_remove("XDG_CONFIG_HOME")
+            .output()
+            .expect("git-all-roots should run")
+    }
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn roots_add_list_rm() {
+    let fixture = Fixture::new();
+    let home = fixture.home();
+
+    let output = fixture.roots(&home, &["roots", "add", "src"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(stdout(&output), "Added root ~/src\n");
+    fixture.roots(&home, &["roots", "add", "work"]);
+    let output = fixture.roots(&home, &["roots", "add", "./src"]);
+    assert!(stdout(&output).starts_with("Already a root"));
+
+    let output = fixture.roots(&home, &["roots"]);
+    assert_eq!(stdout(&output), "~/src\n~/work\n");
+    let registry = std::fs::read_to_string(home.join(".config/git-all/roots.toml")).unwrap();
+    assert!(registry.contains("[[roots]]"));
+    assert!(registry.contains(&format!("path = \"{}\"", home.join("work").display())));
+
+    let output = fixture.roots(&home, &["roots", "rm", "src"]);
+    assert!(output.status.success(), "{output:?}");
+    let output = fixture.roots(&home, &["roots", "list"]);
+    assert_eq!(stdout(&output), "~/work\n");
+
+    let output = fixture.roots(&home, &["roots", "rm", "src"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not a registered root"));
+}
+
+#[test]
+fn status_is_grouped_by_root_from_anywhere() {
+    let fixture = Fixture::new();
+    let home = fixture.home();
+    fixture.roots(&home, &["roots", "add", "src"]);
+    fixture.roots(&home, &["roots", "add", "work"]);
+
+    // Runs across the roots even from inside a repo, instead of passing through
+    let output = fixture.roots(&home.join("src/api"), &["status"]);
+    assert!(output.status.success(), "{output:?}");
+    let lines: Vec<String> = stdout(&output)
+        .lines()
+        .map(|line| line.split(" | ").next().unwrap().trim_end().to_string())
+        .collect();
+    assert_eq!(lines, ["~/src", "  api", "  web", "~/work", "  client-app"]);
+}
+
+#[test]
+fn missing_roots_are_skipped() {
+    let fixture = Fixture::new();
+    let home = fixture.home();
+    std::fs::create_dir_all(home.join("old")).unwrap();
+    fixture.roots(&home, &["roots", "add", "old"]);
+    fixture.roots(&home, &["roots", "add", "work"]);
+    std::fs::remove_dir(home.join("old")).unwrap();
+
+    let output = fixture.roots(&home, &["--format", "ndjson", "status"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("skipping missing root ~/old"));
+    assert!(stdout(&output).contains(&format!("\"root\":\"{}\"", home.join("work").display())));
+}
+
+#[test]
+fn no_roots_registered_fails() {
+    let fixture = Fixture::new();
+    let output = fixture.roots(&fixture.home(), &["status"]);
+    assert_eq!(output.status.code(), Some(9));
+    assert!(stdout(&output).contains("git-all-roots roots add"));
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::{git, make_repo, repo_column, run};
+
+/// clean (main), dirty (main, untracked file), feature (feat/login, with an upstream remote)
+fn workspace() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().expect("temp dir");
+    for name in ["clean", "dirty", "feature"] {
+        make_repo(temp.path(), name);
+    }
+    std::fs::write(temp.path().join("dirty/notes.txt"), "wip").unwrap();
+    let feature = temp.path().join("feature");
+    git(&feature, &["checkout", "-q", "-b", "feat/login"]);
+    git(
+        &feature,
+        &[
+            "remote",
+            "add",
+            "upstream",
+            "git@github.com:org/feature.git",
+        ],
+    );
+    temp
+}
+
+#[test]
+fn only_dirty_and_only_clean_select_by_worktree_state() {
+    let temp = workspace();
+
+    let output = run(temp.path(), &["--only-dirty", "status"]);
+    assert!(output.status.success());
+    assert_eq!(repo_column(&output), ["dirty"]);
+
+    let output = run(temp.path(), &["--only-clean", "status"]);
+    assert!(output.status.success());
+    assert_eq!(repo_column(&output), ["clean", "feature"]);
+}
+
+#[test]
+fn branch_and_remote_predicates() {
+    let temp = workspace();
+
+    let output = run(temp.path(), &["--not-on-branch", "main", "status"]);
+    assert_eq!(repo_column(&output), ["feature"]);
+
+    let output = run(temp.path(), &["--on-branch", "feat/*", "status"]);
+    assert_eq!(repo_column(&output), ["feature"]);
+
+    let output = run(temp.path(), &["--has-remote", "upstream", "status"]);
+    assert_eq!(repo_column(&output), ["feature"]);
+
+    let output = run(temp.path(), &["--remote-host", "github.com", "status"]);
+    assert_eq!(repo_column(&output), ["feature"]);
+}
+
+#[test]
+fn dry_run_lists_excluded_repos_with_reasons() {
+    let temp = workspace();
+
+    let output = run(
+        temp.path(),
+        &["--dry-run", "--only-clean", "--on-branch", "main", "pull"],
+    );
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(
+            "Excluded 2 repositories by selection:\n  \
+             dirty: dirty (1 untracked)\n  \
+             feature: on branch feat/login, not main\n"
+        ),
+        "{stdout}"
+    );
+    let planned: Vec<&str> = stdout.lines().filter(|l| l.starts_with("git ")).collect();
+    assert_eq!(planned.len(), 1, "{stdout}");
+    assert!(planned[0].contains("/clean pull"), "{stdout}");
+}
+
+#[test]
+fn selection_matching_nothing_exits_9() {
+    let temp = workspace();
+    let output = run(temp.path(), &["--has-remote", "nope", "status"]);
+
+    assert_eq!(output.status.code(), Some(9));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("No git repositories matched the selection"));
+}
+
+#[test]
+fn only_dirty_conflicts_with_only_clean() {
+    let temp = workspace();
+    let output = run(temp.path(), &["--only-dirty", "--only-clean", "status"]);
+    assert_eq!(output.status.code(), Some(9));
+}
@@ -129,3 +129,172 @@ fn ssh_url_rewrite_and_multiplexing_override_compose() {
         "expected ControlMaster override alongside --ssh, got:\n{stdout}"
     );
 }
+
+fn make_repo_with_remote(parent: &std::path::Path, name: &str, url: &str) {
+    make_repo(parent, name);
+    let status = Command::new("git")
+        .args(["remote", "add", "origin", url])
+        .current_dir(parent.join(name))
+        .status()
+        .expect("git remote add");
+    assert!(status.success());
+}
+
+/// `core.sshCommand` values in dry-run output, one per git command, in repo order
+fn ssh_commands(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .filter(|line| line.starts_with("git "))
+        .map(|line| {
+            let start =
+                line.find("core.sshCommand=").expect("sshCommand") + "core.sshCommand=".len();
+            let end = line[start..].find('"').expect("closing quote") + start;
+            line[start..end].to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn ssh_masters_spread_repos_round_robin() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    for name in ["a", "b", "c"] {
+        make_repo_with_remote(temp.path(), name, &format!("git@github.com:org/{name}.git"));
+    }
+    make_repo_with_remote(temp.path(), "d", "https://gitlab.internal/org/d.git");
+
+    let (ok, stdout, stderr) = run_dry_run(temp.path(), &["--ssh-masters", "2", "fetch"]);
+    assert!(ok, "stderr: {stderr}");
+
+    let masters: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("ssh "))
+        .collect();
+    assert_eq!(
+        masters.len(),
+        2,
+        "two masters for github.com, got:\n{stdout}"
+    );
+    for master in &masters {
+        assert!(master.contains("-o ControlMaster=yes"), "{master}");
+        assert!(master.ends_with("-N -l git github.com"), "{master}");
+    }
+
+    let commands = ssh_commands(&stdout);
+    assert_eq!(commands.len(), 4, "{stdout}");
+    let path = |command: &str| command.rsplit("ControlPath=").next().unwrap().to_string();
+    assert!(commands[0].starts_with("ssh -o ControlMaster=no -o ControlPath=/"));
+    assert!(masters[0].contains(&path(&commands[0])));
+    assert!(masters[1].contains(&path(&commands[1])));
+    assert_eq!(
+        commands[2], commands[0],
+        "third repo wraps around to the first master"
+    );
+    // HTTPS remotes don't go through the pool and keep the default override
+    assert_eq!(commands[3], "ssh -o ControlMaster=no -o ControlPath=none");
+}
+
+#[test]
+fn ssh_masters_skip_local_commands_and_follow_url_rewrites() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    make_repo_with_remote(temp.path(), "a", "https://gitlab.internal/org/a.git");
+
+    let (ok, stdout, stderr) = run_dry_run(temp.path(), &["--ssh-masters", "2", "status"]);
+    assert!(ok, "stderr: {stderr}");
+    assert!(!stdout.contains("ControlMaster=yes"), "{stdout}");
+    assert!(stdout.contains(OVERRIDE_SUBSTRING), "{stdout}");
+
+    let (ok, stdout, stderr) = run_dry_run(temp.path(), &["--ssh", "--ssh-masters", "2", "fetch"]);
+    assert!(ok, "stderr: {stderr}");
+    let masters: Vec<&str> = stdout
+        .lines()
+        .filter(|line| line.starts_with("ssh "))
+        .collect();
+    assert_eq!(
+        masters.len(),
+        1,
+        "one repo needs one master, got:\n{stdout}"
+    );
+    assert!(
+        masters[0].ends_with("-l git gitlab.internal"),
+        "{}",
+        masters[0]
+    );
+}
+
+/// A stand-in `ssh` that logs masters and sessions instead of connecting
+#[cfg(unix)]
+const FAKE_SSH: &str = r#"#!/bin/sh
+for arg; do
+  case "$arg" in
+    ControlPath=*) path="${arg#ControlPath=}" ;;
+    ControlMaster=yes) master=1 ;;
+  esac
+done
+if [ -n "$master" ]; then
+  sleep 30 &
+  trap 'echo "stop $path" >> "$SSH_LOG"; kill $!; rm -f "$path"; exit 0' TERM
+  echo "start $path" >> "$SSH_LOG"
+  : > "$path"
+  wait $!
+  exit 0
+fi
+echo "session $path" >> "$SSH_LOG"
+exit 1
+"#;
+
+#[cfg(unix)]
+#[test]
+fn ssh_masters_are_opened_first_and_closed_at_exit() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = tempfile::tempdir().expect("temp dir");
+    let bin = temp.path().join("bin");
+    let repos = temp.path().join("repos");
+    std::fs::create_dir_all(&bin).unwrap();
+    std::fs::create_dir_all(&repos).unwrap();
+    std::fs::write(bin.join("ssh"), FAKE_SSH).unwrap();
+    std::fs::set_permissions(bin.join("ssh"), std::fs::Permissions::from_mode(0o755)).unwrap();
+    for name in ["a", "b", "c"] {
+        make_repo_with_remote(&repos, name, &format!("git@github.com:org/{name}.git"));
+    }
+    let log = temp.path().join("ssh.log");
+    let path = format!(
+        "{}:{}",
+        bin.display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_git-all"))
+        .args(["--ssh-masters", "2", "fetch"])
+        .current_dir(&repos)
+        .env("PATH", path)
+        .env("SSH_LOG", &log)
+        .output()
+        .expect("git-all should run");
+    assert!(!output.status.success(), "the fake ssh can't fetch");
+
+    let log = std::fs::read_to_string(&log).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    let count = |prefix: &str| lines.iter().filter(|line| line.starts_with(prefix)).count();
+    assert_eq!(count("start "), 2, "{log}");
+    assert_eq!(count("session "), 3, "{log}");
+    assert_eq!(count("stop "), 2, "{log}");
+    let first_session = lines
+        .iter()
+        .position(|line| line.starts_with("session "))
+        .unwrap();
+    assert!(
+        lines[..first_session]
+            .iter()
+            .all(|line| line.starts_with("start ")),
+        "{log}"
+    );
+    for line in lines.iter().filter(|line| line.starts_with("session ")) {
+        let socket = line.trim_start_matches("session ");
+        assert!(log.contains(&format!("start {socket}")), "{log}");
+        assert!(
+            !std::path::Path::new(socket).exists(),
+            "socket left behind: {socket}"
+        );
+    }
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use common::{commit, git, rows, run};
+
+/// An `upstream` repo and clones of it under `ws/` in every state sync cares about
+fn workspace() -> tempfile::TempDir {
+    let temp = tempfile::tempdir().expect("temp dir");
+    let root = temp.path();
+    git(root, &["init", "-q", "-b", "main", "upstream"]);
+    let upstream = root.join("upstream");
+    commit(&upstream, "one");
+
+    for name in [
+        "behind", "dirty", "diverged", "ahead", "current", "detached",
+    ] {
+        git(root, &["clone", "-q", "upstream", &format!("ws/{name}")]);
+    }
+    git(&root.join("ws"), &["init", "-q", "-b", "main", "local"]);
+    commit(&root.join("ws/local"), "local");
+
+    commit(&upstream, "two");
+    commit(&upstream, "three");
+
+    std::fs::write(root.join("ws/dirty/notes.txt"), "wip").unwrap();
+    commit(&root.join("ws/diverged"), "mine");
+    commit(&root.join("ws/ahead"), "mine");
+    git(&root.join("ws/ahead"), &["fetch", "-q"]);
+    git(
+        &root.join("ws/ahead"),
+        &["reset", "-q", "--hard", "origin/main"],
+    );
+    commit(&root.join("ws/ahead"), "mine");
+    git(&root.join("ws/current"), &["pull", "-q"]);
+    git(&root.join("ws/detached"), &["checkout", "-q", "--detach"]);
+    temp
+}
+
+#[test]
+fn sync_fast_forwards_only_when_safe() {
+    let temp = workspace();
+    let ws = temp.path().join("ws");
+    let before_diverged = git(&ws.join("diverged"), &["rev-parse", "HEAD"]);
+
+    let output = run(&ws, &["sync"]);
+    assert!(output.status.success(), "{output:?}");
+    let rows = rows(&output);
+    let expected = [
+        ("ahead", "up to date, 1 ahead"),
+        ("behind", "fast-forwarded 2 commits"),
+        ("current", "up to date"),
+        ("detached", "skipped: detached"),
+        ("dirty", "skipped: dirty"),
+        ("diverged", "skipped: diverged 1/2"),
+        ("local", "skipped: no upstream"),
+    ];
+    let expected: Vec<(String, String)> = expected
+        .iter()
+        .map(|(repo, message)| (repo.to_string(), message.to_string()))
+        .collect();
+    assert_eq!(rows, expected);
+
+    let upstream_head = git(&temp.path().join("upstream"), &["rev-parse", "HEAD"]);
+    assert_eq!(
+        git(&ws.join("behind"), &["rev-parse", "HEAD"]),
+        upstream_head
+    );
+    assert_ne!(
+        git(&ws.join("dirty"), &["rev-parse", "HEAD"]),
+        upstream_head
+    );
+    assert_eq!(
+        git(&ws.join("diverged"), &["rev-parse", "HEAD"]),
+        before_diverged
+    );
+    // Skipped repos were still fetched
+    assert_eq!(
+        git(&ws.join("dirty"), &["rev-parse", "origin/main"]),
+        upstream_head
+    );
+}
+
+#[test]
+fn sync_reports_json_fields() {
+    let temp = workspace();
+    let output = run(&temp.path().join("ws"), &["--format", "json", "sync"]);
+    assert!(output.status.success(), "{output:?}");
+
+    let records: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    let record = |name: &str| {
+        records
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|record| record["repo"] == name)
+            .unwrap_or_else(|| panic!("no record for {name}"))
+            .clone()
+    };
+    assert_eq!(record("behind")["fields"]["sync"], "fast-forwarded");
+    assert_eq!(record("behind")["fields"]["commits"], 2);
+    assert_eq!(record("diverged")["fields"]["sync"], "skipped");
+    assert_eq!(record("diverged")["fields"]["reason"], "diverged 1/2");
+    assert_eq!(record("current")["fields"]["sync"], "up-to-date");
+    // stdout is git fetch's own
+    assert_eq!(record("current")["stdout"], "");
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use common::{make_repo_with_alias, run};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+#[test]
+fn timeout_kills_hung_repo_and_reports_it() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    // Background child verifies the whole process group is killed, not just git
+    make_repo_with_alias(temp.path(), "fast", "!true");
+    make_repo_with_alias(temp.path(), "hung", "!sleep 10 & sleep 10");
+
+    let started = Instant::now();
+    let output = run(temp.path(), &["--timeout", "300ms", "check"]);
+
+    assert!(
+        started.elapsed() < Duration::from_secs(5),
+        "timeout did not stop the hung repo: {:?}",
+        started.elapsed()
+    );
+    assert_eq!(output.status.code(), Some(1));
+
+    // Passthrough commands print their labelled output verbatim; the timeout
+    // replaces the missing git stderr
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stdout.contains("==> hung <=="), "{stdout}");
+    assert!(stderr.contains("timed out after 300ms"), "{stderr}");
+    assert!(!stderr.contains("ERROR"), "{stderr}");
+    assert!(
+        stdout.contains("1 of 2 repositories failed: hung"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn timed_out_repo_is_an_ordinary_failed_record() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    make_repo_with_alias(temp.path(), "hung", "!sleep 10");
+
+    let output = run(
+        temp.path(),
+        &["--timeout", "200ms", "--format", "json", "check"],
+    );
+
+    assert_eq!(output.status.code(), Some(1));
+    let records: serde_json::Value = serde_json::from_slice(&output.stdout).expect("json");
+    let record = &records[0];
+    assert_eq!(record["success"], false, "{record}");
+    assert_eq!(record["error"], serde_json::Value::Null, "{record}");
+    assert_eq!(record["stderr"], "timed out after 200ms\n", "{record}");
+}
+
+#[test]
+fn timeout_is_recorded_in_trace() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    make_repo_with_alias(temp.path(), "hung", "!sleep 10");
+    let trace_path = temp.path().join("trace.log");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_git-all"))
+        .args(["check"])
+        .env("GIT_ALL_TIMEOUT", "200ms")
+        .env("GIT_ALL_TRACE", "1")
+        .env("GIT_ALL_TRACE_FILE", &trace_path)
+        .current_dir(temp.path())
+        .output()
+        .expect("git-all should run");
+
+    assert_eq!(output.status.code(), Some(1));
+    let trace = std::fs::read_to_string(&trace_path).expect("trace file");
+    assert!(trace.contains("timed_out=true"), "{trace}");
+}
@@ -21,7 +21,10 @@ fn trace_reports_ordered_wait_for_blocked_repos() {
     let stderr = String::from_utf8_lossy(&output.stderr);
 
     assert_eq!(
-        stdout.lines().count(),
+        stdout
+            .lines()
+            .filter(|line| line.ends_with(" done"))
+            .count(),
         3,
         "expected one output line per repo: {stdout}"
     );
@@ -0,0 +1,77 @@
+mod common;
+
+use common::{git, make_repo, run};
+
+/// The dry-run line for the repo named `name`
+fn line_for<'a>(stdout: &'a str, name: &str) -> &'a str {
+    stdout
+        .lines()
+        .find(|line| line.contains(&format!("/{name} fetch")))
+        .unwrap_or_else(|| panic!("no dry-run line for {name}:\n{stdout}"))
+}
+
+#[test]
+fn ssh_rewrites_each_remote_host() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    for (name, remote) in [
+        ("api", "https://gitlab.internal/team/api.git"),
+        ("web", "https://github.com/team/web.git"),
+    ] {
+        make_repo(temp.path(), name);
+        git(
+            &temp.path().join(name),
+            &["remote", "add", "origin", remote],
+        );
+    }
+
+    let output = run(temp.path(), &["--dry-run", "--ssh", "fetch"]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let api = line_for(&stdout, "api");
+    assert!(
+        api.contains("url.git@gitlab.internal:.insteadOf=https://gitlab.internal/"),
+        "{api}"
+    );
+    assert!(!api.contains("github.com"), "{api}");
+    let web = line_for(&stdout, "web");
+    assert!(
+        web.contains("url.git@github.com:.insteadOf=https://github.com/"),
+        "{web}"
+    );
+}
+
+#[test]
+fn url_host_sets_user_and_port() {
+    let temp = tempfile::tempdir().expect("temp dir");
+    make_repo(temp.path(), "api");
+    git(
+        &temp.path().join("api"),
+        &["remote", "add", "origin", "https://gitea.lan/team/api.git"],
+    );
+
+    let output = run(
+        temp.path(),
+        &[
+            "--dry-run",
+            "--ssh",
+            "--url-host",
+            "ops@gitea.lan:2222",
+            "fetch",
+        ],
+    );
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("url.ssh://ops@gitea.lan:2222/.insteadOf=https://gitea.lan/"),
+        "{stdout}"
+    );
+
+    let output = run(temp.path(), &["--dry-run", "--https", "fetch"]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("url.https://gitea.lan/.insteadOf=git@gitea.lan:"),
+        "{stdout}"
+    );
+}